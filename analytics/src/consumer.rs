@@ -1,4 +1,5 @@
 use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::Headers;
 use rdkafka::{ClientConfig, Message};
 
 pub async fn start() {
@@ -29,9 +30,16 @@ async fn consume(consumer: StreamConsumer) {
         match consumer.recv().await {
             Err(e) => println!("{:?}", e),
             Ok(message) => {
+                // Set by the todo servers, to join events with the requests that caused them
+                let request_id = message
+                    .headers()
+                    .and_then(|headers| headers.iter().find(|header| header.key == "x-request-id"))
+                    .and_then(|header| header.value)
+                    .map(String::from_utf8_lossy)
+                    .unwrap_or_default();
                 match message.payload_view::<str>() {
-                    None => println!("None Message"),
-                    Some(Ok(msg)) => println!("Message consumed {:?}", msg),
+                    None => println!("[{}] None Message", request_id),
+                    Some(Ok(msg)) => println!("[{}] Message consumed {:?}", request_id, msg),
                     Some(Err(e)) => println!("Error Parsing {:?}", e),
                 }
                 consumer
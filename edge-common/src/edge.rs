@@ -0,0 +1,224 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::Display,
+    future::{ready, Future, Ready},
+    net::IpAddr,
+    pin::Pin,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+use actix_web::{
+    body::EitherBody,
+    dev::{Extensions, Service, ServiceRequest, ServiceResponse, Transform},
+    error::PayloadError,
+    http::{header::ContentType, StatusCode},
+    rt::net::TcpStream,
+    web, HttpRequest, HttpResponse, ResponseError,
+};
+
+/// Protection against oversized requests and slow or greedy clients, applied before anything
+/// is forwarded.
+#[derive(Debug, Clone)]
+pub struct EdgeLimits {
+    /// Total size of the request header names and values; larger headers get `431`.
+    pub max_header_bytes: usize,
+    /// Larger request bodies get `413`.
+    pub max_body_bytes: usize,
+    /// Time a client has to send the request head before the connection is closed with `408`.
+    pub header_read_timeout: Duration,
+    /// Time a client has to send the request body before it gets `408`.
+    pub body_read_timeout: Duration,
+    /// Open connections allowed per client IP; requests on further connections get `429`.
+    pub max_connections_per_ip: Option<usize>,
+}
+
+impl Default for EdgeLimits {
+    fn default() -> Self {
+        EdgeLimits {
+            max_header_bytes: 16 * 1024,
+            max_body_bytes: 1024 * 1024,
+            header_read_timeout: Duration::from_secs(5),
+            body_read_timeout: Duration::from_secs(10),
+            max_connections_per_ip: None,
+        }
+    }
+}
+
+/// Open connections per client IP.
+#[derive(Default)]
+pub struct ConnectionCounts(Mutex<HashMap<IpAddr, usize>>);
+
+/// Stored with each connection, so the count is decremented when the connection closes.
+struct Connection {
+    counts: Arc<ConnectionCounts>,
+    ip: IpAddr,
+    over_limit: bool,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let mut counts = self.counts.0.lock().unwrap();
+        if let Some(count) = counts.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// `HttpServer::on_connect` callback counting the connections of each client IP.
+pub fn count_connections(
+    counts: Arc<ConnectionCounts>,
+    max_per_ip: usize,
+) -> impl Fn(&dyn Any, &mut Extensions) + Send + Sync + 'static {
+    move |connection, extensions| {
+        let Some(ip) = connection.downcast_ref::<TcpStream>().and_then(|stream| stream.peer_addr().ok()).map(|addr| addr.ip())
+        else {
+            return;
+        };
+        let mut open = counts.0.lock().unwrap();
+        let count = open.entry(ip).or_default();
+        *count += 1;
+        let over_limit = *count > max_per_ip;
+        drop(open);
+        extensions.insert(Connection { counts: counts.clone(), ip, over_limit });
+    }
+}
+
+impl EdgeLimits {
+    /// Rejects requests arriving on a connection over the per-IP cap, or with oversized headers.
+    fn check_request(&self, req: &HttpRequest) -> Result<(), EdgeError> {
+        if req.conn_data::<Connection>().is_some_and(|connection| connection.over_limit) {
+            return Err(EdgeError::TooManyConnections);
+        }
+        let header_bytes: usize = req.headers().iter().map(|(name, value)| name.as_str().len() + value.len()).sum();
+        if header_bytes > self.max_header_bytes {
+            return Err(EdgeError::HeadersTooLarge);
+        }
+        Ok(())
+    }
+
+    /// Reads the whole request body, within the size and time limits.
+    async fn read_body(&self, payload: web::Payload) -> Result<web::Bytes, EdgeError> {
+        match actix_web::rt::time::timeout(self.body_read_timeout, payload.to_bytes_limited(self.max_body_bytes)).await {
+            Err(_) => Err(EdgeError::BodyTimeout),
+            Ok(Err(_)) => Err(EdgeError::BodyTooLarge),
+            Ok(Ok(Err(err))) => match err.as_error::<PayloadError>() {
+                Some(PayloadError::Overflow) => Err(EdgeError::BodyTooLarge),
+                _ => Err(EdgeError::Payload(err.to_string())),
+            },
+            Ok(Ok(Ok(bytes))) => Ok(bytes),
+        }
+    }
+}
+
+/// Middleware applying the [`EdgeLimits`] before the wrapped service sees a request, so rejected
+/// requests are never counted or forwarded. The body is read here and handed on in one piece.
+pub struct EdgeCheck {
+    limits: EdgeLimits,
+}
+
+impl EdgeCheck {
+    pub fn new(limits: EdgeLimits) -> Self {
+        EdgeCheck { limits }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for EdgeCheck
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = EdgeCheckMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(EdgeCheckMiddleware { service: Rc::new(service), limits: Rc::new(self.limits.clone()) }))
+    }
+}
+
+pub struct EdgeCheckMiddleware<S> {
+    service: Rc<S>,
+    limits: Rc<EdgeLimits>,
+}
+
+impl<S, B> Service<ServiceRequest> for EdgeCheckMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let limits = self.limits.clone();
+        Box::pin(async move {
+            if let Err(err) = limits.check_request(req.request()) {
+                return Ok(req.error_response(err).map_into_right_body());
+            }
+            let payload = req.extract::<web::Payload>().await?;
+            match limits.read_body(payload).await {
+                Ok(bytes) => req.set_payload(bytes.into()),
+                Err(err) => return Ok(req.error_response(err).map_into_right_body()),
+            }
+            service.call(req).await.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum EdgeError {
+    TooManyConnections,
+    HeadersTooLarge,
+    BodyTooLarge,
+    BodyTimeout,
+    Payload(String),
+}
+
+impl Display for EdgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeError::TooManyConnections => write!(f, "Too many connections from this IP"),
+            EdgeError::HeadersTooLarge => write!(f, "Request headers are too large"),
+            EdgeError::BodyTooLarge => write!(f, "Request body is too large"),
+            EdgeError::BodyTimeout => write!(f, "Request body was not received in time"),
+            EdgeError::Payload(err) => write!(f, "Failed to read request body: {}", err),
+        }
+    }
+}
+
+impl ResponseError for EdgeError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            EdgeError::TooManyConnections => StatusCode::TOO_MANY_REQUESTS,
+            EdgeError::HeadersTooLarge => StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            EdgeError::BodyTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            EdgeError::BodyTimeout => StatusCode::REQUEST_TIMEOUT,
+            EdgeError::Payload(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        // Don't keep reading from a client that sends too much or too slowly
+        if !matches!(self, EdgeError::Payload(_)) {
+            response.force_close();
+        }
+        response.insert_header(ContentType::html()).body(self.to_string())
+    }
+}
@@ -0,0 +1,165 @@
+use std::{fs, io, str::FromStr};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::Deserialize;
+
+/// Header rewrites applied to forwarded requests and to the responses sent back, per route.
+///
+/// Loaded from a JSON file (see `headers.example.json`). Values may contain the placeholders
+/// `{client_ip}`, `{backend}`, `{request_id}`, `{method}` and `{path}`.
+///
+/// The default rules strip nothing: upstream headers such as `server`, `x-powered-by` and
+/// `server-status` reach clients unless a `remove` action drops them, as the example file does.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderRules {
+    routes: Vec<RouteRules>,
+}
+
+#[derive(Debug, Clone)]
+struct RouteRules {
+    path_prefix: String,
+    request: Vec<HeaderAction>,
+    response: Vec<HeaderAction>,
+}
+
+#[derive(Debug, Clone)]
+enum HeaderAction {
+    /// Appends a value, keeping existing ones.
+    Add(HeaderName, String),
+    /// Replaces all values.
+    Set(HeaderName, String),
+    Remove(HeaderName),
+    /// Moves all values to another name.
+    Rename(HeaderName, HeaderName),
+}
+
+#[derive(Deserialize)]
+struct RulesFile {
+    routes: Vec<RouteFile>,
+}
+
+#[derive(Deserialize)]
+struct RouteFile {
+    #[serde(default)]
+    path_prefix: String,
+    #[serde(default)]
+    request: Vec<ActionFile>,
+    #[serde(default)]
+    response: Vec<ActionFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum ActionFile {
+    Add { name: String, value: String },
+    Set { name: String, value: String },
+    Remove { name: String },
+    Rename { from: String, to: String },
+}
+
+/// Values substituted for the placeholders of a request.
+pub struct TemplateValues<'a> {
+    pub client_ip: &'a str,
+    pub backend: &'a str,
+    pub request_id: &'a str,
+    pub method: &'a str,
+    pub path: &'a str,
+}
+
+impl HeaderRules {
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let file: RulesFile =
+            serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let routes = file
+            .routes
+            .into_iter()
+            .map(|route| {
+                Ok(RouteRules {
+                    path_prefix: route.path_prefix,
+                    request: route.request.into_iter().map(HeaderAction::parse).collect::<io::Result<_>>()?,
+                    response: route.response.into_iter().map(HeaderAction::parse).collect::<io::Result<_>>()?,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(HeaderRules { routes })
+    }
+
+    /// Rewrites the headers forwarded upstream.
+    pub fn apply_request(&self, headers: &mut HeaderMap, values: &TemplateValues) {
+        for route in self.matching(values.path) {
+            apply(&route.request, headers, values);
+        }
+    }
+
+    /// Rewrites the headers of the response sent back to the client.
+    pub fn apply_response(&self, headers: &mut HeaderMap, values: &TemplateValues) {
+        for route in self.matching(values.path) {
+            apply(&route.response, headers, values);
+        }
+    }
+
+    fn matching<'a>(&'a self, path: &'a str) -> impl Iterator<Item = &'a RouteRules> {
+        self.routes.iter().filter(move |route| path.starts_with(&route.path_prefix))
+    }
+}
+
+impl HeaderAction {
+    fn parse(action: ActionFile) -> io::Result<Self> {
+        Ok(match action {
+            ActionFile::Add { name, value } => HeaderAction::Add(header_name(&name)?, value),
+            ActionFile::Set { name, value } => HeaderAction::Set(header_name(&name)?, value),
+            ActionFile::Remove { name } => HeaderAction::Remove(header_name(&name)?),
+            ActionFile::Rename { from, to } => HeaderAction::Rename(header_name(&from)?, header_name(&to)?),
+        })
+    }
+}
+
+fn header_name(name: &str) -> io::Result<HeaderName> {
+    HeaderName::from_str(name)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid header name '{}'", name)))
+}
+
+fn apply(actions: &[HeaderAction], headers: &mut HeaderMap, values: &TemplateValues) {
+    for action in actions {
+        match action {
+            HeaderAction::Add(name, template) => {
+                if let Some(value) = render(template, values) {
+                    headers.append(name.clone(), value);
+                }
+            }
+            HeaderAction::Set(name, template) => {
+                if let Some(value) = render(template, values) {
+                    headers.insert(name.clone(), value);
+                }
+            }
+            HeaderAction::Remove(name) => {
+                headers.remove(name);
+            }
+            HeaderAction::Rename(from, to) => {
+                let moved: Vec<HeaderValue> = headers.get_all(from).iter().cloned().collect();
+                headers.remove(from);
+                for value in moved {
+                    headers.append(to.clone(), value);
+                }
+            }
+        }
+    }
+}
+
+/// Fills in the placeholders; values that don't make a valid header are skipped.
+fn render(template: &str, values: &TemplateValues) -> Option<HeaderValue> {
+    let rendered = template
+        .replace("{client_ip}", values.client_ip)
+        .replace("{backend}", values.backend)
+        .replace("{request_id}", values.request_id)
+        .replace("{method}", values.method)
+        .replace("{path}", values.path);
+    match HeaderValue::from_str(&rendered) {
+        Ok(value) => Some(value),
+        Err(_) => {
+            eprintln!("Skipping header rule, '{}' is not a valid header value", rendered);
+            None
+        }
+    }
+}
@@ -0,0 +1,8 @@
+//! Request handling shared by the proxies in front of the todo service, the rate limiter and the
+//! load balancer, and by the todo service itself.
+
+pub mod edge;
+
+pub mod headers;
+
+pub mod request_id;
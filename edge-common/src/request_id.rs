@@ -0,0 +1,93 @@
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use actix_web::{
+    dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    FromRequest, HttpMessage, HttpRequest,
+};
+use uuid::Uuid;
+
+/// Header correlating a request across the rate limiter, load balancer, todo servers and Kafka.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Accepts the caller's `X-Request-Id` or generates one, sets it on the request so proxies forward
+/// it, makes it available to handlers as [`RequestId`], echoes it in the response and logs it with
+/// the response status.
+pub struct RequestIdHeader;
+
+/// The id of the current request, extracted in handlers to tag logs and Kafka messages.
+#[derive(Clone)]
+pub struct RequestId(pub String);
+
+impl FromRequest for RequestId {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let id = req.extensions().get::<RequestId>().cloned();
+        ready(Ok(id.unwrap_or_else(|| RequestId(Uuid::new_v4().to_string()))))
+    }
+}
+
+/// Ids from callers are kept if they are reasonably short and printable.
+fn accepted(value: &HeaderValue) -> Option<&str> {
+    let id = value.to_str().ok()?;
+    (!id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic())).then_some(id)
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdHeader
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = RequestIdMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware { service }))
+    }
+}
+
+pub struct RequestIdMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let id = match req.headers().get(REQUEST_ID_HEADER).and_then(accepted) {
+            Some(id) => id.to_string(),
+            None => Uuid::new_v4().to_string(),
+        };
+        let value = HeaderValue::from_str(&id).unwrap();
+        req.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value.clone());
+        req.extensions_mut().insert(RequestId(id.clone()));
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let mut res = fut.await?;
+            res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            println!("[{}] {} {} {}", id, res.request().method(), res.request().path(), res.status().as_u16());
+            Ok(res)
+        })
+    }
+}
@@ -1,24 +1,51 @@
-use std::{fmt::Display, sync::atomic::{AtomicUsize, Ordering}};
+use std::{fmt::Display, sync::{atomic::{AtomicUsize, Ordering}, Arc}};
 use actix_web::{
     http::header::ContentType,
     web::{self},
     App, HttpRequest, HttpResponse, HttpServer, ResponseError,
 };
-use reqwest::Client;
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Client,
+};
+
+pub use edge_common::edge;
+use edge::{ConnectionCounts, EdgeCheck, EdgeError, EdgeLimits};
+
+pub use edge_common::headers;
+use headers::{HeaderRules, TemplateValues};
+
+pub use edge_common::request_id;
+use request_id::{RequestIdHeader, REQUEST_ID_HEADER};
 
 pub struct LoadBalancer {
     port: u16,
     servers: Vec<String>,
+    edge: EdgeLimits,
+    header_rules: HeaderRules,
 }
 
 struct AppState {
     servers: Vec<String>,
     current_index: AtomicUsize,
+    header_rules: HeaderRules,
 }
 
 impl LoadBalancer {
     pub fn new(port: u16, servers: Vec<String>) -> Self {
-        LoadBalancer { port, servers }
+        LoadBalancer { port, servers, edge: EdgeLimits::default(), header_rules: HeaderRules::default() }
+    }
+
+    /// Rewrites request and response headers. By default only `load-balancer-status: ok` is added to responses.
+    pub fn with_header_rules(mut self, header_rules: HeaderRules) -> Self {
+        self.header_rules = header_rules;
+        self
+    }
+
+    /// Sets the request size, read timeout and per-IP connection limits. Defaults to [`EdgeLimits::default`].
+    pub fn with_edge_limits(mut self, edge: EdgeLimits) -> Self {
+        self.edge = edge;
+        self
     }
 
     pub fn uri(&self) -> String {
@@ -29,71 +56,108 @@ impl LoadBalancer {
         let data = web::Data::new(AppState {
             servers: self.servers.clone(),
             current_index: AtomicUsize::new(0),
+            header_rules: self.header_rules.clone(),
         });
 
-        HttpServer::new(move || {
+        let edge = self.edge.clone();
+        let mut server = HttpServer::new(move || {
             App::new()
                 .default_service(web::to(Self::handler))
                 .app_data(data.clone())
+                .wrap(EdgeCheck::new(edge.clone()))
+                .wrap(RequestIdHeader)
         })
-        .bind(("127.0.0.1", self.port))
-        .unwrap()
-        .run()
-        .await
-        .unwrap();
+        .client_request_timeout(self.edge.header_read_timeout);
+        if let Some(max_per_ip) = self.edge.max_connections_per_ip {
+            server = server.on_connect(edge::count_connections(Arc::new(ConnectionCounts::default()), max_per_ip));
+        }
+        server.bind(("127.0.0.1", self.port)).unwrap().run().await.unwrap();
     }
 
     async fn handler(
         req: HttpRequest,
         data: web::Data<AppState>,
-        bytes: web::Bytes,
+        payload: web::Payload,
     ) -> Result<HttpResponse, LoadBalanceError> {
+        // Already read within the edge limits
+        let bytes = payload.to_bytes().await.map_err(|err| EdgeError::Payload(err.to_string()))?;
+
         // Get the next server in round-robin order
         let current_index = data.current_index.fetch_add(1, Ordering::SeqCst) % data.servers.len();
         let server = &data.servers[current_index];
         let uri = format!("{}{}", server, req.uri());
+        let client_ip = req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_default();
+        let values = TemplateValues {
+            client_ip: &client_ip,
+            backend: server,
+            request_id: req.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()).unwrap_or_default(),
+            method: req.method().as_str(),
+            path: req.path(),
+        };
+
+        let mut headers: HeaderMap = req.headers().into();
+        data.header_rules.apply_request(&mut headers, &values);
 
         let client = Client::new();
         let request_builder = client
             .request(req.method().clone(), uri)
-            .headers(req.headers().into())
+            .headers(headers)
             .body(bytes);
 
         let response = request_builder.send().await?;
 
+        let mut headers = response.headers().clone();
+        headers.append("load-balancer-status", HeaderValue::from_static("ok"));
+        data.header_rules.apply_response(&mut headers, &values);
+
         let mut response_builder = HttpResponse::build(response.status());
-        for h in response.headers().iter() {
+        for h in headers.iter() {
             response_builder.append_header(h);
         }
-        response_builder.append_header(("load-balancer-status", "ok"));
         let body = response.bytes().await?;
         Ok(response_builder.body(body))
     }
 }
 
 #[derive(Debug)]
-struct LoadBalanceError {
-    inner: reqwest::Error,
+enum LoadBalanceError {
+    Forward(reqwest::Error),
+    Edge(EdgeError),
 }
 
 impl Display for LoadBalanceError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Forwarding error: {}", self.inner)
+        match self {
+            LoadBalanceError::Forward(err) => write!(f, "Forwarding error: {}", err),
+            LoadBalanceError::Edge(err) => write!(f, "{}", err),
+        }
     }
 }
 
 impl From<reqwest::Error> for LoadBalanceError {
     fn from(value: reqwest::Error) -> Self {
-        LoadBalanceError { inner: value }
+        LoadBalanceError::Forward(value)
+    }
+}
+
+impl From<EdgeError> for LoadBalanceError {
+    fn from(value: EdgeError) -> Self {
+        LoadBalanceError::Edge(value)
     }
 }
 
 impl ResponseError for LoadBalanceError {
     fn status_code(&self) -> reqwest::StatusCode {
-        reqwest::StatusCode::INTERNAL_SERVER_ERROR
+        match self {
+            LoadBalanceError::Forward(_) => reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+            LoadBalanceError::Edge(err) => err.status_code(),
+        }
     }
 
     fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        if let LoadBalanceError::Edge(err) = self {
+            return err.error_response();
+        }
         HttpResponse::build(self.status_code())
             .insert_header(ContentType::html())
             .body(self.to_string())
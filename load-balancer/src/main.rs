@@ -1,7 +1,9 @@
 use dotenv::dotenv;
 use regex::Regex;
 
-use load_balancer::LoadBalancer;
+use std::time::Duration;
+
+use load_balancer::{edge::EdgeLimits, headers::HeaderRules, LoadBalancer};
 
 #[actix_web::main]
 async fn main(){
@@ -39,7 +41,31 @@ async fn main(){
     }
     println!("backend server urls: {:?}", servers);
 
-    let load_balancer = LoadBalancer::new(port, servers);
+    // Request size and read time limits, and connections per client IP
+    let env_number = |name: &str| -> Option<u64> {
+        std::env::var(name).ok().map(|v| v.parse().unwrap_or_else(|_| panic!("{} must be a number", name)))
+    };
+    let defaults = EdgeLimits::default();
+    let edge = EdgeLimits {
+        max_header_bytes: env_number("MAX_HEADER_BYTES").map_or(defaults.max_header_bytes, |v| v as usize),
+        max_body_bytes: env_number("MAX_BODY_BYTES").map_or(defaults.max_body_bytes, |v| v as usize),
+        header_read_timeout: env_number("HEADER_READ_TIMEOUT_MS")
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.header_read_timeout),
+        body_read_timeout: env_number("BODY_READ_TIMEOUT_MS")
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.body_read_timeout),
+        max_connections_per_ip: env_number("MAX_CONNECTIONS_PER_IP").map(|v| v as usize),
+    };
+
+    let mut load_balancer = LoadBalancer::new(port, servers).with_edge_limits(edge);
+
+    // Optional request and response header rewrites
+    if let Ok(rules_file) = std::env::var("HEADER_RULES_FILE") {
+        let rules = HeaderRules::from_file(&rules_file).expect("Failed to load HEADER_RULES_FILE");
+        load_balancer = load_balancer.with_header_rules(rules);
+    }
+
     println!("Load Balancer running on {}", load_balancer.uri());
     load_balancer.run().await
 }
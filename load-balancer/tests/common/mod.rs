@@ -0,0 +1,35 @@
+#![allow(dead_code)]
+
+use std::{net::TcpListener, time::Duration};
+
+use load_balancer::LoadBalancer;
+use wiremock::{matchers::any, Mock, MockServer, ResponseTemplate};
+
+pub fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+/// A backend answering `200` with a `server-status: ok` header, like the todo servers.
+pub async fn backend() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200).insert_header("server-status", "ok"))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Starts the load balancer in the background and waits until it accepts connections.
+pub async fn start(load_balancer: LoadBalancer) -> String {
+    let uri = load_balancer.uri();
+    let addr = uri.trim_start_matches("http://").to_string();
+    actix_web::rt::spawn(async move { load_balancer.run().await });
+
+    for _ in 0..50 {
+        if actix_web::rt::net::TcpStream::connect(&addr).await.is_ok() {
+            return uri;
+        }
+        actix_web::rt::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("load balancer did not start on {}", uri);
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{backend, free_port};
+use load_balancer::{edge::EdgeLimits, LoadBalancer};
+use wiremock::MockServer;
+
+async fn start(edge: EdgeLimits) -> (String, MockServer) {
+    let server = backend().await;
+    let load_balancer = LoadBalancer::new(free_port(), vec![server.uri()]).with_edge_limits(edge);
+    (common::start(load_balancer).await, server)
+}
+
+#[actix_web::test]
+async fn rejects_large_bodies_with_413() {
+    let (uri, _server) = start(EdgeLimits { max_body_bytes: 16, ..Default::default() }).await;
+    let client = reqwest::Client::new();
+
+    let response = client.post(format!("{}/todo", uri)).body("x".repeat(17)).send().await.unwrap();
+    assert_eq!(response.status(), 413);
+    let response = client.post(format!("{}/todo", uri)).body("x".repeat(16)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[actix_web::test]
+async fn rejects_large_headers_with_431() {
+    let (uri, _server) = start(EdgeLimits { max_header_bytes: 256, ..Default::default() }).await;
+
+    let response = reqwest::Client::new().get(format!("{}/todos", uri)).header("x-big", "x".repeat(300)).send().await.unwrap();
+    assert_eq!(response.status(), 431);
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::{backend, free_port, start};
+use load_balancer::{headers::HeaderRules, LoadBalancer};
+
+#[actix_web::test]
+async fn strips_server_status_and_names_the_backend() {
+    let path = std::env::temp_dir().join(format!("load-balancer-test-headers-{}.json", free_port()));
+    std::fs::write(
+        &path,
+        r#"{ "routes": [{ "response": [
+            { "action": "remove", "name": "server-status" },
+            { "action": "set", "name": "x-served-by", "value": "{backend}" }
+        ] }] }"#,
+    )
+    .unwrap();
+    let rules = HeaderRules::from_file(&path.to_string_lossy()).unwrap();
+    let (first, second) = (backend().await, backend().await);
+    let uri = start(LoadBalancer::new(free_port(), vec![first.uri(), second.uri()]).with_header_rules(rules)).await;
+
+    for expected in [first.uri(), second.uri()] {
+        let response = reqwest::get(format!("{}/todos", uri)).await.unwrap();
+        assert!(!response.headers().contains_key("server-status"));
+        assert_eq!(response.headers()["x-served-by"], expected.as_str());
+        assert_eq!(response.headers()["load-balancer-status"], "ok");
+    }
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use common::{free_port, start};
+use load_balancer::{request_id::REQUEST_ID_HEADER, LoadBalancer};
+use wiremock::{matchers::header, Mock, MockServer, ResponseTemplate};
+
+#[actix_web::test]
+async fn forwards_and_echoes_the_request_id() {
+    let server = MockServer::start().await;
+    Mock::given(header(REQUEST_ID_HEADER, "abc-123")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+    let uri = start(LoadBalancer::new(free_port(), vec![server.uri()])).await;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/todos", uri))
+        .header(REQUEST_ID_HEADER, "abc-123")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()[REQUEST_ID_HEADER], "abc-123");
+
+    // Requests without an id get a generated one
+    let response = reqwest::get(format!("{}/todos", uri)).await.unwrap();
+    assert_eq!(response.headers()[REQUEST_ID_HEADER].len(), 36);
+}
@@ -0,0 +1,99 @@
+//! Compares per-request latency of the old connection handling (a `Mutex<Client>` locked to
+//! open a new multiplexed connection on every request) against one shared `ConnectionManager`.
+//!
+//! Needs a running Redis: `REDIS_URL=redis://localhost:6379 cargo bench`
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use redis::{aio::ConnectionManager, Client as RedisClient, RedisResult};
+use tokio::sync::Mutex;
+
+const CONCURRENCY: usize = 32;
+const REQUESTS_PER_TASK: usize = 200;
+
+/// The INCR + EXPIRE pipeline issued by the rate limiter for one quota.
+async fn count(con: &mut impl redis::aio::ConnectionLike, key: &str) -> RedisResult<u64> {
+    let (count,): (u64,) = redis::pipe().incr(key, 1).expire(key, 10).ignore().query_async(con).await?;
+    Ok(count)
+}
+
+async fn mutex_client(client: RedisClient) -> Vec<Duration> {
+    let client = Arc::new(Mutex::new(client));
+    let tasks = (0..CONCURRENCY).map(|task| {
+        let client = client.clone();
+        tokio::spawn(async move {
+            let key = format!("bench:mutex:{}", task);
+            let mut latencies = Vec::with_capacity(REQUESTS_PER_TASK);
+            for _ in 0..REQUESTS_PER_TASK {
+                let start = Instant::now();
+                let mut con = client.lock().await.get_multiplexed_async_connection().await.unwrap();
+                count(&mut con, &key).await.unwrap();
+                latencies.push(start.elapsed());
+            }
+            latencies
+        })
+    });
+    collect(tasks).await
+}
+
+async fn connection_manager(client: RedisClient) -> Vec<Duration> {
+    let manager = ConnectionManager::new(client).await.unwrap();
+    let tasks = (0..CONCURRENCY).map(|task| {
+        let manager = manager.clone();
+        tokio::spawn(async move {
+            let key = format!("bench:manager:{}", task);
+            let mut latencies = Vec::with_capacity(REQUESTS_PER_TASK);
+            for _ in 0..REQUESTS_PER_TASK {
+                let start = Instant::now();
+                let mut con = manager.clone();
+                count(&mut con, &key).await.unwrap();
+                latencies.push(start.elapsed());
+            }
+            latencies
+        })
+    });
+    collect(tasks).await
+}
+
+async fn collect(tasks: impl Iterator<Item = tokio::task::JoinHandle<Vec<Duration>>>) -> Vec<Duration> {
+    let mut latencies = Vec::new();
+    for task in tasks.collect::<Vec<_>>() {
+        latencies.extend(task.await.unwrap());
+    }
+    latencies.sort();
+    latencies
+}
+
+fn report(name: &str, latencies: &[Duration], total: Duration) {
+    let percentile = |p: usize| latencies[(latencies.len() - 1) * p / 100];
+    println!(
+        "{:<20} {:>8.0} req/s   p50 {:>9.3?}   p99 {:>9.3?}   max {:>9.3?}",
+        name,
+        latencies.len() as f64 / total.as_secs_f64(),
+        percentile(50),
+        percentile(99),
+        latencies[latencies.len() - 1],
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    let redis_url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
+    let client = RedisClient::open(redis_url.clone()).expect("Invalid Redis URL");
+    if client.get_multiplexed_async_connection().await.is_err() {
+        eprintln!("Redis not reachable at {}, skipping benchmark", redis_url);
+        return;
+    }
+
+    println!("{} tasks x {} requests", CONCURRENCY, REQUESTS_PER_TASK);
+
+    let start = Instant::now();
+    let latencies = mutex_client(client.clone()).await;
+    report("mutex + connect", &latencies, start.elapsed());
+
+    let start = Instant::now();
+    let latencies = connection_manager(client).await;
+    report("connection manager", &latencies, start.elapsed());
+}
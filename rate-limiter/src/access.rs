@@ -0,0 +1,121 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    net::IpAddr,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+use ipnet::IpNet;
+use serde::Deserialize;
+
+use crate::store::Store;
+
+/// IP ranges and API keys that skip rate limiting or are refused outright.
+#[derive(Debug, Default)]
+pub struct AccessLists {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+    exempt_keys: HashSet<String>,
+}
+
+/// What the access lists decide for a request.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Access {
+    /// Forward without counting against any quota.
+    Bypass,
+    /// Reject with `403 Forbidden`.
+    Deny,
+    /// Apply the usual rate limits.
+    Limit,
+}
+
+#[derive(Deserialize)]
+struct AccessFile {
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+    #[serde(default)]
+    exempt_keys: Vec<String>,
+}
+
+impl AccessLists {
+    /// Builds the lists from IPs or CIDR ranges (`10.0.0.0/8`, `::1`) and API keys.
+    pub fn new(allow: Vec<String>, deny: Vec<String>, exempt_keys: Vec<String>) -> io::Result<Self> {
+        Ok(AccessLists {
+            allow: parse_nets(allow)?,
+            deny: parse_nets(deny)?,
+            exempt_keys: exempt_keys.into_iter().collect(),
+        })
+    }
+
+    /// Denied IPs win over allowed ones and exempt API keys.
+    pub fn check(&self, ip: Option<IpAddr>, api_key: Option<&str>) -> Access {
+        let in_list = |nets: &[IpNet]| ip.is_some_and(|ip| nets.iter().any(|net| net.contains(&ip)));
+        if in_list(&self.deny) {
+            Access::Deny
+        } else if in_list(&self.allow) || api_key.is_some_and(|key| self.exempt_keys.contains(key)) {
+            Access::Bypass
+        } else {
+            Access::Limit
+        }
+    }
+}
+
+fn parse_nets(entries: Vec<String>) -> io::Result<Vec<IpNet>> {
+    entries
+        .iter()
+        .map(|entry| {
+            IpNet::from_str(entry)
+                .or_else(|_| IpAddr::from_str(entry).map(IpNet::from))
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid IP or CIDR range '{}'", entry)))
+        })
+        .collect()
+}
+
+/// Where the access lists are (re)loaded from.
+#[derive(Debug, Clone)]
+pub enum AccessSource {
+    /// A JSON file: `{ "allow": ["10.0.0.0/8"], "deny": ["203.0.113.7"], "exempt_keys": ["ci-key"] }`
+    File(String),
+    /// The Redis sets `<prefix>:allow`, `<prefix>:deny` and `<prefix>:exempt-keys`.
+    Redis(String),
+}
+
+impl AccessSource {
+    pub async fn load(&self, store: &dyn Store) -> io::Result<AccessLists> {
+        match self {
+            AccessSource::File(path) => {
+                let content = fs::read_to_string(path)?;
+                let file: AccessFile = serde_json::from_str(&content)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                AccessLists::new(file.allow, file.deny, file.exempt_keys)
+            }
+            AccessSource::Redis(prefix) => {
+                let members = |set: &'static str| async move {
+                    store
+                        .smembers(&format!("{}:{}", prefix, set))
+                        .await
+                        .map_err(|e| io::Error::other(e.to_string()))
+                };
+                AccessLists::new(members("allow").await?, members("deny").await?, members("exempt-keys").await?)
+            }
+        }
+    }
+}
+
+/// The current access lists, swapped out whenever they are reloaded.
+#[derive(Default)]
+pub struct AccessControl {
+    lists: RwLock<Arc<AccessLists>>,
+}
+
+impl AccessControl {
+    pub fn current(&self) -> Arc<AccessLists> {
+        self.lists.read().unwrap().clone()
+    }
+
+    pub fn replace(&self, lists: AccessLists) {
+        *self.lists.write().unwrap() = Arc::new(lists);
+    }
+}
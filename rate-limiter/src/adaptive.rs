@@ -0,0 +1,125 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Settings of the adaptive global limit, in requests per second forwarded by this instance.
+#[derive(Debug, Clone)]
+pub struct AdaptiveConfig {
+    pub initial_limit: u64,
+    pub min_limit: u64,
+    pub max_limit: u64,
+    /// Average upstream latency above which the upstream counts as struggling.
+    pub target_latency: Duration,
+    /// Share of 5xx responses and forwarding errors above which the upstream counts as struggling.
+    pub max_error_rate: f64,
+    /// Added to the limit after a healthy interval in which the limit was nearly reached.
+    pub increase: u64,
+    /// Multiplies the limit after an unhealthy interval.
+    pub decrease_factor: f64,
+    /// How often the limit is re-evaluated.
+    pub interval: Duration,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        AdaptiveConfig {
+            initial_limit: 100,
+            min_limit: 1,
+            max_limit: 10_000,
+            target_latency: Duration::from_millis(500),
+            max_error_rate: 0.05,
+            increase: 5,
+            decrease_factor: 0.7,
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Global requests-per-second limit following AIMD (additive increase, multiplicative
+/// decrease), driven by the latency and errors of the responses this instance forwards.
+pub(crate) struct AdaptiveLimiter {
+    config: AdaptiveConfig,
+    state: Mutex<State>,
+}
+
+struct State {
+    limit: u64,
+    // Requests admitted in the current one-second window
+    window_start: Instant,
+    admitted: u64,
+    // Samples of the current evaluation interval
+    interval_start: Instant,
+    responses: u64,
+    errors: u64,
+    latency: Duration,
+    // Most requests admitted in a single window of the interval
+    peak: u64,
+}
+
+impl AdaptiveLimiter {
+    pub fn new(config: AdaptiveConfig) -> Self {
+        let now = Instant::now();
+        let limit = config.initial_limit.clamp(config.min_limit, config.max_limit);
+        AdaptiveLimiter {
+            config,
+            state: Mutex::new(State {
+                limit,
+                window_start: now,
+                admitted: 0,
+                interval_start: now,
+                responses: 0,
+                errors: 0,
+                latency: Duration::ZERO,
+                peak: 0,
+            }),
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.state.lock().unwrap().limit
+    }
+
+    /// Counts a request against the current second, returning false once the limit is reached.
+    pub fn try_admit(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.window_start.elapsed() >= Duration::from_secs(1) {
+            state.window_start = Instant::now();
+            state.admitted = 0;
+        }
+        if state.admitted >= state.limit {
+            return false;
+        }
+        state.admitted += 1;
+        state.peak = state.peak.max(state.admitted);
+        true
+    }
+
+    /// Records the outcome of a forwarded request and adjusts the limit once per interval.
+    pub fn record(&self, latency: Duration, failed: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.responses += 1;
+        state.errors += failed as u64;
+        state.latency += latency;
+
+        if state.interval_start.elapsed() < self.config.interval {
+            return;
+        }
+
+        let error_rate = state.errors as f64 / state.responses as f64;
+        let average_latency = state.latency / state.responses as u32;
+        if error_rate > self.config.max_error_rate || average_latency > self.config.target_latency {
+            let decreased = (state.limit as f64 * self.config.decrease_factor) as u64;
+            state.limit = decreased.max(self.config.min_limit);
+        } else if state.peak * 10 >= state.limit * 8 {
+            // Only grow while the limit is actually in use, so it can't drift up while idle
+            state.limit = (state.limit + self.config.increase).min(self.config.max_limit);
+        }
+
+        state.interval_start = Instant::now();
+        state.responses = 0;
+        state.errors = 0;
+        state.latency = Duration::ZERO;
+        state.peak = state.admitted;
+    }
+}
@@ -0,0 +1,195 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use actix_web::{
+    delete, get, put,
+    web::{self, Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{hot_clients_key, AppState, ClientPlan, RateLimitError};
+
+/// Admin API for support staff, mounted under `/admin` when an admin token is configured.
+/// Clients are addressed by the id used in their counters: `ip:<address>` or `key:<api key>`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .service(get_client)
+            .service(reset_client)
+            .service(raise_client_limit)
+            .service(remove_client_override)
+            .service(hot_clients),
+    );
+}
+
+#[derive(Deserialize)]
+pub struct RaiseLimitBody {
+    /// Factor applied to every quota limit of the client.
+    pub multiplier: f64,
+    /// How long the raised limit lasts.
+    pub ttl_secs: u64,
+}
+
+#[derive(Deserialize)]
+pub struct HotClientsQuery {
+    pub n: Option<usize>,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Checks the `Authorization: Bearer <token>` header against the configured admin token.
+fn authorized(req: &HttpRequest, state: &AppState) -> bool {
+    let token = req
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    matches!((token, &state.admin_token), (Some(token), Some(expected)) if token == expected)
+}
+
+fn unauthorized() -> HttpResponse {
+    HttpResponse::Unauthorized().json(json!({ "error": "Invalid admin token." }))
+}
+
+/// Resolves a client id to its plan, without counting a request.
+async fn client_plan(state: &AppState, client_id: &str) -> Result<Result<ClientPlan, HttpResponse>, RateLimitError> {
+    match client_id.split_once(':') {
+        Some(("ip", ip)) => Ok(Ok(state.limits.plan(None, None, ip))),
+        Some(("key", key)) => match state.limits.lookup_tier(&*state.limits.store, Some(key)).await? {
+            Some(tier) => Ok(Ok(state.limits.plan(Some(key), Some(tier), ""))),
+            None => Ok(Err(HttpResponse::NotFound().json(json!({ "error": "Unknown API key." })))),
+        },
+        _ => Ok(Err(HttpResponse::BadRequest().json(json!({
+            "error": "Client id must look like ip:<address> or key:<api key>."
+        })))),
+    }
+}
+
+#[get("/clients/{client_id}")]
+async fn get_client(req: HttpRequest, state: Data<AppState>, path: Path<String>) -> Result<HttpResponse, RateLimitError> {
+    if !authorized(&req, &state) {
+        return Ok(unauthorized());
+    }
+    let plan = match client_plan(&state, &path).await? {
+        Ok(plan) => plan,
+        Err(response) => return Ok(response),
+    };
+
+    let now = now();
+    let keys: Vec<String> = plan.quotas.iter().map(|quota| plan.counter_key(quota, now)).collect();
+    let counts = state.limits.store.counts(&keys).await?;
+    let multiplier = state.limits.store.get(&plan.override_key()).await?;
+
+    let mut raised = plan.clone();
+    raised.raise(multiplier.clone());
+    let quotas: Vec<_> = raised
+        .quotas
+        .iter()
+        .zip(counts)
+        .map(|(quota, count)| {
+            json!({
+                "name": quota.name,
+                "limit": quota.limit,
+                "count": count,
+                "remaining": quota.limit.saturating_sub(count),
+                "reset_in": quota.window_secs - now % quota.window_secs,
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "client_id": plan.id,
+        "tier": plan.tier,
+        "multiplier": multiplier.and_then(|m| m.parse::<f64>().ok()),
+        "quotas": quotas,
+    })))
+}
+
+/// Clears the client's counters of the current windows.
+#[delete("/clients/{client_id}")]
+async fn reset_client(req: HttpRequest, state: Data<AppState>, path: Path<String>) -> Result<HttpResponse, RateLimitError> {
+    if !authorized(&req, &state) {
+        return Ok(unauthorized());
+    }
+    let plan = match client_plan(&state, &path).await? {
+        Ok(plan) => plan,
+        Err(response) => return Ok(response),
+    };
+
+    let now = now();
+    let keys: Vec<String> = plan.quotas.iter().map(|quota| plan.counter_key(quota, now)).collect();
+    state.limits.store.delete(&keys).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Temporarily multiplies the client's limits.
+#[put("/clients/{client_id}/override")]
+async fn raise_client_limit(
+    req: HttpRequest,
+    state: Data<AppState>,
+    path: Path<String>,
+    body: Json<RaiseLimitBody>,
+) -> Result<HttpResponse, RateLimitError> {
+    if !authorized(&req, &state) {
+        return Ok(unauthorized());
+    }
+    if body.multiplier <= 0.0 || body.ttl_secs == 0 {
+        return Ok(HttpResponse::BadRequest().json(json!({
+            "error": "multiplier and ttl_secs must be positive."
+        })));
+    }
+    let plan = match client_plan(&state, &path).await? {
+        Ok(plan) => plan,
+        Err(response) => return Ok(response),
+    };
+
+    let ttl = Duration::from_secs(body.ttl_secs);
+    state.limits.store.set(&plan.override_key(), &body.multiplier.to_string(), ttl).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("/clients/{client_id}/override")]
+async fn remove_client_override(
+    req: HttpRequest,
+    state: Data<AppState>,
+    path: Path<String>,
+) -> Result<HttpResponse, RateLimitError> {
+    if !authorized(&req, &state) {
+        return Ok(unauthorized());
+    }
+    let plan = match client_plan(&state, &path).await? {
+        Ok(plan) => plan,
+        Err(response) => return Ok(response),
+    };
+
+    state.limits.store.delete(&[plan.override_key()]).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// The clients with the most requests during the current minute.
+#[get("/hot")]
+async fn hot_clients(
+    req: HttpRequest,
+    state: Data<AppState>,
+    query: Query<HotClientsQuery>,
+) -> Result<HttpResponse, RateLimitError> {
+    if !authorized(&req, &state) {
+        return Ok(unauthorized());
+    }
+
+    let n = query.n.unwrap_or(10);
+    if !(1..=100).contains(&n) {
+        return Ok(HttpResponse::BadRequest().json(json!({
+            "error": "n must be between 1 and 100."
+        })));
+    }
+
+    let top = state.limits.store.ztop(&hot_clients_key(now()), n).await?;
+    let clients: Vec<_> = top
+        .into_iter()
+        .map(|(client_id, requests)| json!({ "client_id": client_id, "requests": requests }))
+        .collect();
+    Ok(HttpResponse::Ok().json(clients))
+}
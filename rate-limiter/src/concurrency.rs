@@ -0,0 +1,165 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use actix_web::HttpResponse;
+use serde_json::json;
+
+use crate::store::{Store, StoreError};
+
+/// How often a queued request retries to acquire its leases.
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Caps on requests in flight to the upstream at the same time.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyConfig {
+    /// Maximum in-flight requests of one client (API key or IP).
+    pub per_client: Option<u64>,
+    /// Maximum in-flight requests across all clients and rate limiter instances.
+    pub global: Option<u64>,
+    /// How many requests of this instance may wait for a free slot at once.
+    pub max_queued: usize,
+    /// How long a queued request waits before it is rejected.
+    pub queue_timeout: Duration,
+    /// Expiry of a lease, so slots held by a crashed instance are eventually freed.
+    /// Should exceed the slowest expected upstream response.
+    pub lease_ttl: Duration,
+}
+
+/// Hands out leases on in-flight slots, stored in Redis so limits hold across instances.
+pub(crate) struct ConcurrencyLimiter {
+    config: ConcurrencyConfig,
+    instance: String,
+    next_lease: AtomicU64,
+    queued: AtomicUsize,
+}
+
+/// Leases held by one request, released when dropped.
+pub(crate) struct Leases {
+    store: Arc<dyn Store>,
+    held: Vec<(String, String)>,
+}
+
+impl Leases {
+    /// No leases, for requests that are let through without concurrency limiting.
+    pub fn none(store: Arc<dyn Store>) -> Self {
+        Leases { store, held: Vec::new() }
+    }
+}
+
+impl Drop for Leases {
+    fn drop(&mut self) {
+        if self.held.is_empty() {
+            return;
+        }
+        let store = self.store.clone();
+        let held = std::mem::take(&mut self.held);
+        tokio::spawn(async move {
+            for (key, lease) in held {
+                if let Err(err) = store.release(&key, &lease).await {
+                    eprintln!("Failed to release concurrency lease, it expires on its own: {}", err);
+                }
+            }
+        });
+    }
+}
+
+/// A request refused because a concurrency cap stayed exhausted.
+pub(crate) struct ConcurrencyRejection {
+    global: bool,
+    limit: u64,
+}
+
+impl ConcurrencyRejection {
+    pub fn rule(&self) -> &'static str {
+        if self.global { "concurrency_global" } else { "concurrency_client" }
+    }
+
+    /// `429` when the client itself is over its cap, `503` when the upstream as a whole is.
+    pub fn response(&self) -> HttpResponse {
+        let mut response = if self.global {
+            HttpResponse::ServiceUnavailable()
+        } else {
+            HttpResponse::TooManyRequests()
+        };
+        response.json(json!({
+            "error": "Too many concurrent requests. Please try again later.",
+            "scope": if self.global { "global" } else { "client" },
+            "limit": self.limit,
+        }))
+    }
+}
+
+/// Decrements the queue length when a queued request stops waiting.
+struct QueueSlot<'a>(&'a AtomicUsize);
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        ConcurrencyLimiter {
+            config,
+            instance: format!("{}-{}", std::process::id(), started.as_nanos()),
+            next_lease: AtomicU64::new(0),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    /// Acquires a slot of the client's and of the global cap, waiting in the queue while
+    /// either is exhausted.
+    pub async fn acquire(
+        &self,
+        store: Arc<dyn Store>,
+        client_id: &str,
+    ) -> Result<Result<Leases, ConcurrencyRejection>, StoreError> {
+        let lease = format!("{}-{}", self.instance, self.next_lease.fetch_add(1, Ordering::Relaxed));
+        // Keys are hash tagged like the window counters, so each lives in a single cluster slot
+        let caps: Vec<(String, u64, bool)> = [
+            self.config.per_client.map(|limit| (format!("concurrency:{{{}}}", client_id), limit, false)),
+            self.config.global.map(|limit| ("concurrency:{global}".to_string(), limit, true)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let deadline = Instant::now() + self.config.queue_timeout;
+        let mut queue_slot = None;
+        loop {
+            let mut leases = Leases::none(store.clone());
+            let mut exhausted = None;
+            for (key, limit, global) in &caps {
+                if store.acquire(key, &lease, *limit, self.config.lease_ttl).await? {
+                    leases.held.push((key.clone(), lease.clone()));
+                } else {
+                    exhausted = Some(ConcurrencyRejection { global: *global, limit: *limit });
+                    break;
+                }
+            }
+            let Some(rejection) = exhausted else {
+                return Ok(Ok(leases));
+            };
+            // Give back the slots already taken before waiting
+            drop(leases);
+
+            if queue_slot.is_none() {
+                if self.queued.fetch_add(1, Ordering::SeqCst) >= self.config.max_queued {
+                    self.queued.fetch_sub(1, Ordering::SeqCst);
+                    return Ok(Err(rejection));
+                }
+                queue_slot = Some(QueueSlot(&self.queued));
+            }
+            if Instant::now() + QUEUE_POLL_INTERVAL > deadline {
+                return Ok(Err(rejection));
+            }
+            tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::Duration;
+use rdkafka::{
+    message::{Header, OwnedHeaders},
+    producer::{FutureProducer, FutureRecord},
+    util::Timeout,
+    ClientConfig,
+};
+use serde::Serialize;
+
+use crate::request_id::REQUEST_ID_HEADER;
+
+/// Publishes rejected requests to a Kafka topic, so the analytics service can report on
+/// abusive clients.
+pub(crate) struct RejectionEvents {
+    producer: FutureProducer,
+    topic: String,
+}
+
+/// A rejected request, sent as JSON keyed by the client id.
+#[derive(Serialize)]
+pub(crate) struct RejectionEvent {
+    pub request_id: String,
+    pub client_id: String,
+    pub key_type: &'static str,
+    pub rule: String,
+    pub method: String,
+    pub path: String,
+    /// Unix time in seconds.
+    pub timestamp: u64,
+}
+
+impl RejectionEvents {
+    pub fn new(brokers: &str, topic: String) -> Self {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", brokers);
+
+        let producer: FutureProducer = config.create().expect("Failure in creating producer");
+
+        RejectionEvents { producer, topic }
+    }
+
+    /// Sends the event in the background, so rejections aren't slowed down by Kafka.
+    pub fn publish(&self, event: RejectionEvent) {
+        let producer = self.producer.clone();
+        let topic = self.topic.clone();
+        actix_web::rt::spawn(async move {
+            let payload = serde_json::to_string(&event).unwrap();
+            let headers = OwnedHeaders::new().insert(Header { key: REQUEST_ID_HEADER, value: Some(&event.request_id) });
+            let record = FutureRecord::to(&topic).payload(&payload).key(&event.client_id).headers(headers);
+            if let Err((err, _)) = producer.send(record, Timeout::After(Duration::from_secs(2))).await {
+                eprintln!("Failed to publish rejection event: {}", err);
+            }
+        });
+    }
+}
@@ -1,30 +1,266 @@
-use std::{fmt::Display, sync::Arc};
+use std::{
+    fmt::Display,
+    io,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use actix_web::{
     http::header::ContentType,
     web::{self, Data},
     App, HttpRequest, HttpResponse, HttpServer, ResponseError,
 };
-use redis::{AsyncCommands, Client as RedisClient};
-use reqwest::Client;
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Client,
+};
 use serde_json::json;
-use tokio::sync::Mutex;
 
+pub mod access;
+use access::{AccessControl, AccessSource};
+
+pub mod adaptive;
+use adaptive::{AdaptiveConfig, AdaptiveLimiter};
+
+mod admin;
+
+pub mod concurrency;
+use concurrency::{ConcurrencyConfig, ConcurrencyLimiter, ConcurrencyRejection, Leases};
+
+pub use edge_common::edge;
+use edge::{ConnectionCounts, EdgeCheck, EdgeError, EdgeLimits};
+
+mod events;
+use events::RejectionEvents;
+
+pub use edge_common::headers;
+use headers::{HeaderRules, TemplateValues};
+
+mod metrics;
+use metrics::{MeteredStore, Metrics};
+
+pub mod middleware;
+pub use middleware::RateLimit;
+
+pub use edge_common::request_id;
+use request_id::{RequestIdHeader, REQUEST_ID_HEADER};
+
+pub mod store;
+use store::{Counter, MemoryStore, Store, StoreConfig, StoreError};
+
+pub mod tiers;
+use tiers::{KeySource, Quota, Tier, TierConfig};
+
+/// Header carrying the client's API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Window of the default quota applied to clients without a known API key.
+const DEFAULT_WINDOW_SECS: u64 = 10;
+
+/// How long per-minute hot client counts are kept.
+const HOT_CLIENTS_TTL: Duration = Duration::from_secs(120);
+
+/// Standalone proxy forwarding the requests let through by the [`RateLimit`] middleware.
 pub struct RateLimiter {
     port: u16,
     forward_url: String,
-    redis_url: String,
+    limits: RateLimitConfig,
+    admin_token: Option<String>,
+    edge: EdgeLimits,
+    header_rules: HeaderRules,
+}
+
+/// Settings of the rate limits, started as a [`RateLimit`] middleware.
+#[derive(Clone)]
+pub struct RateLimitConfig {
+    store: StoreConfig,
     request_limit: usize,
+    tiers: Option<TierConfig>,
+    failure_policy: FailurePolicy,
+    access: Option<(AccessSource, Duration)>,
+    concurrency: Option<ConcurrencyConfig>,
+    adaptive: Option<AdaptiveConfig>,
+    events: Option<(String, String)>,
 }
 
 struct AppState {
     forward_url: String,
-    redis_client: Arc<Mutex<RedisClient>>,
+    limits: Arc<Limits>,
+    admin_token: Option<String>,
+    header_rules: HeaderRules,
+}
+
+/// Rate limiting state shared by all workers of a server.
+struct Limits {
+    store: Arc<dyn Store>,
     request_limit: usize,
+    tiers: Option<TierConfig>,
+    failure_policy: FailurePolicy,
+    fallback: Arc<MemoryStore>,
+    access: AccessControl,
+    concurrency: Option<ConcurrencyLimiter>,
+    adaptive: Option<AdaptiveLimiter>,
+    metrics: Metrics,
+    events: Option<RejectionEvents>,
+}
+
+impl RateLimitConfig {
+    /// Limits clients without a known API key to `request_limit` requests per IP and window.
+    pub fn new(store: StoreConfig, request_limit: usize) -> Self {
+        RateLimitConfig {
+            store,
+            request_limit,
+            tiers: None,
+            failure_policy: FailurePolicy::Open,
+            access: None,
+            concurrency: None,
+            adaptive: None,
+            events: None,
+        }
+    }
+
+    /// Enables per-API-key plans. Clients without a known key keep the default per-IP limit.
+    pub fn with_tiers(mut self, tiers: TierConfig) -> Self {
+        self.tiers = Some(tiers);
+        self
+    }
+
+    /// Sets how requests are handled while the store (Redis) is unreachable. Defaults to [`FailurePolicy::Open`].
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Enables IP/CIDR allow and deny lists and exempt API keys, reloaded every `reload_every`.
+    pub fn with_access_lists(mut self, source: AccessSource, reload_every: Duration) -> Self {
+        self.access = Some((source, reload_every));
+        self
+    }
+
+    /// Caps requests in flight to the upstream, per client and globally.
+    pub fn with_concurrency_limits(mut self, concurrency: ConcurrencyConfig) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /// Adds a global requests-per-second limit that adapts to upstream latency and errors.
+    pub fn with_adaptive_limit(mut self, adaptive: AdaptiveConfig) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    /// Publishes every rejected request as a JSON event to `topic` on the Kafka `brokers`.
+    pub fn with_rejection_events(mut self, brokers: String, topic: String) -> Self {
+        self.events = Some((brokers, topic));
+        self
+    }
+
+    /// Connects the store and loads the access lists, returning the middleware to wrap apps
+    /// with. Must be called inside the actix runtime, which reloads the access lists.
+    pub async fn start(&self) -> io::Result<RateLimit> {
+        let metrics = Metrics::new();
+        let limits = Arc::new(Limits {
+            store: Arc::new(MeteredStore::new(self.store.build(), &metrics)),
+            request_limit: self.request_limit,
+            tiers: self.tiers.clone(),
+            failure_policy: self.failure_policy,
+            fallback: Arc::new(MemoryStore::default()),
+            access: AccessControl::default(),
+            concurrency: self.concurrency.clone().map(ConcurrencyLimiter::new),
+            adaptive: self.adaptive.clone().map(AdaptiveLimiter::new),
+            metrics,
+            events: self.events.as_ref().map(|(brokers, topic)| RejectionEvents::new(brokers, topic.clone())),
+        });
+
+        if let Some((source, reload_every)) = self.access.clone() {
+            // A broken file is a configuration error, while Redis may just not be up yet
+            match source.load(&*limits.store).await {
+                Ok(lists) => limits.access.replace(lists),
+                Err(err) if matches!(source, AccessSource::File(_)) => return Err(err),
+                Err(err) => eprintln!("Failed to load access lists: {}", err),
+            }
+
+            let limits = limits.clone();
+            actix_web::rt::spawn(async move {
+                let mut interval = actix_web::rt::time::interval(reload_every);
+                loop {
+                    interval.tick().await;
+                    match source.load(&*limits.store).await {
+                        Ok(lists) => limits.access.replace(lists),
+                        Err(err) => eprintln!("Failed to reload access lists, keeping the previous ones: {}", err),
+                    }
+                }
+            });
+        }
+
+        Ok(RateLimit::new(limits))
+    }
 }
 
 impl RateLimiter {
-    pub fn new(port: u16, forward_url: String, redis_url: String, request_limit: usize) -> Self {
-        RateLimiter { port, forward_url, redis_url, request_limit }
+    pub fn new(port: u16, forward_url: String, store: StoreConfig, request_limit: usize) -> Self {
+        RateLimiter {
+            port,
+            forward_url,
+            limits: RateLimitConfig::new(store, request_limit),
+            admin_token: None,
+            edge: EdgeLimits::default(),
+            header_rules: HeaderRules::default(),
+        }
+    }
+
+    /// See [`RateLimitConfig::with_tiers`].
+    pub fn with_tiers(mut self, tiers: TierConfig) -> Self {
+        self.limits = self.limits.with_tiers(tiers);
+        self
+    }
+
+    /// See [`RateLimitConfig::with_failure_policy`].
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.limits = self.limits.with_failure_policy(failure_policy);
+        self
+    }
+
+    /// See [`RateLimitConfig::with_access_lists`].
+    pub fn with_access_lists(mut self, source: AccessSource, reload_every: Duration) -> Self {
+        self.limits = self.limits.with_access_lists(source, reload_every);
+        self
+    }
+
+    /// See [`RateLimitConfig::with_concurrency_limits`].
+    pub fn with_concurrency_limits(mut self, concurrency: ConcurrencyConfig) -> Self {
+        self.limits = self.limits.with_concurrency_limits(concurrency);
+        self
+    }
+
+    /// See [`RateLimitConfig::with_adaptive_limit`].
+    pub fn with_adaptive_limit(mut self, adaptive: AdaptiveConfig) -> Self {
+        self.limits = self.limits.with_adaptive_limit(adaptive);
+        self
+    }
+
+    /// See [`RateLimitConfig::with_rejection_events`].
+    pub fn with_rejection_events(mut self, brokers: String, topic: String) -> Self {
+        self.limits = self.limits.with_rejection_events(brokers, topic);
+        self
+    }
+
+    /// Enables the `/admin` API, authenticated with `Authorization: Bearer <token>`.
+    pub fn with_admin_token(mut self, token: String) -> Self {
+        self.admin_token = Some(token);
+        self
+    }
+
+    /// Rewrites request and response headers. By default only `rate-limiter-status: ok` is added to responses.
+    pub fn with_header_rules(mut self, header_rules: HeaderRules) -> Self {
+        self.header_rules = header_rules;
+        self
+    }
+
+    /// Sets the request size, read timeout and per-IP connection limits. Defaults to [`EdgeLimits::default`].
+    pub fn with_edge_limits(mut self, edge: EdgeLimits) -> Self {
+        self.edge = edge;
+        self
     }
 
     pub fn uri(&self) -> String {
@@ -32,87 +268,348 @@ impl RateLimiter {
     }
 
     pub async fn run(&self) -> Result<(), std::io::Error> {
-        let redis_client = RedisClient::open(self.redis_url.clone()).expect("Invalid Redis URL");
+        // The handler feeds the adaptive limit, timing only the upstream call
+        let rate_limit = self.limits.start().await?.without_outcome_recording();
         let data = Data::new(AppState {
             forward_url: self.forward_url.clone(),
-            redis_client: Arc::new(Mutex::new(redis_client)),
-            request_limit: self.request_limit,
+            limits: rate_limit.limits(),
+            admin_token: self.admin_token.clone(),
+            header_rules: self.header_rules.clone(),
         });
 
-        HttpServer::new(move || {
+        let admin_enabled = self.admin_token.is_some();
+        let edge = self.edge.clone();
+        let mut server = HttpServer::new(move || {
             App::new()
-                .default_service(web::to(Self::handler))
+                .configure(|cfg| {
+                    if admin_enabled {
+                        admin::configure(cfg)
+                    }
+                })
+                .route("/rate-limiter/metrics", web::get().to(Metrics::metrics_handler))
+                // Everything else is rate limited and forwarded, unless the edge limits reject it first
+                .service(
+                    web::scope("")
+                        .wrap(rate_limit.clone())
+                        .wrap(EdgeCheck::new(edge.clone()))
+                        .default_service(web::to(Self::handler)),
+                )
                 .app_data(data.clone())
+                .wrap(RequestIdHeader)
         })
-        .bind(("127.0.0.1", self.port))
-        .unwrap()
-        .run()
-        .await
+        .client_request_timeout(self.edge.header_read_timeout);
+        if let Some(max_per_ip) = self.edge.max_connections_per_ip {
+            server = server.on_connect(edge::count_connections(Arc::new(ConnectionCounts::default()), max_per_ip));
+        }
+        server.bind(("127.0.0.1", self.port)).unwrap().run().await
     }
 
     async fn handler(
         req: HttpRequest,
         data: Data<AppState>,
-        bytes: web::Bytes,
+        payload: web::Payload,
     ) -> Result<HttpResponse, RateLimitError> {
-        let client_ip = req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_else(|| "unknown".to_string());
-        let redis_client = data.redis_client.clone();
-        let mut con = redis_client.lock().await.get_multiplexed_async_connection().await.unwrap();
-
-        // Increment request count and check against limit
-        let _: () = con.incr(client_ip.clone(), 1).await.unwrap();
-        let _: () = con.expire(client_ip.clone(), 10).await.unwrap();
-        let request_count: i32 = con.get(client_ip.clone()).await.unwrap_or(0);
+        // Already read within the edge limits
+        let bytes = payload.to_bytes().await.map_err(|err| EdgeError::Payload(err.to_string()))?;
 
-        if request_count > data.request_limit as i32 {
-            return Ok(HttpResponse::TooManyRequests().json(json!({
-                "error": "Rate limit exceeded. Please try again later."
-            })));
+        let started = Instant::now();
+        let result = Self::forward(&req, &data, bytes).await;
+        if let Some(adaptive) = &data.limits.adaptive {
+            let failed = result.as_ref().map_or(true, |response| response.status().is_server_error());
+            adaptive.record(started.elapsed(), failed);
         }
+        result
+    }
 
+    async fn forward(req: &HttpRequest, data: &AppState, bytes: web::Bytes) -> Result<HttpResponse, RateLimitError> {
         let uri: String = format!("{}{}", data.forward_url, req.uri());
+        let client_ip = req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_default();
+        let values = TemplateValues {
+            client_ip: &client_ip,
+            backend: &data.forward_url,
+            request_id: req.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()).unwrap_or_default(),
+            method: req.method().as_str(),
+            path: req.path(),
+        };
+
+        let mut headers: HeaderMap = req.headers().into();
+        data.header_rules.apply_request(&mut headers, &values);
 
         let client = Client::new();
         let request_builder = client
             .request(req.method().clone(), uri)
-            .headers(req.headers().into())
+            .headers(headers)
             .body(bytes);
 
         let response = request_builder.send().await?;
 
+        let mut headers = response.headers().clone();
+        headers.append("rate-limiter-status", HeaderValue::from_static("ok"));
+        data.header_rules.apply_response(&mut headers, &values);
+
         let mut response_builder = HttpResponse::build(response.status());
-        for h in response.headers().iter() {
+        for h in headers.iter() {
             response_builder.append_header(h);
         }
-        response_builder.append_header(("rate-limiter-status", "ok"));
         let body = response.bytes().await?;
         Ok(response_builder.body(body))
     }
 }
 
+/// Sorted set counting requests per client during the minute of `now`.
+fn hot_clients_key(now: u64) -> String {
+    format!("rate-limit:hot:{}", now / 60)
+}
+
+impl Limits {
+    /// Counts the request in the store, falling back to the failure policy if it is unreachable.
+    async fn limit(
+        &self,
+        api_key: Option<&str>,
+        client_ip: &str,
+        now: u64,
+    ) -> Result<Verdict, RateLimitError> {
+        match self.check(&*self.store, api_key, client_ip, now).await {
+            Ok(verdict) => Ok(verdict),
+            Err(err) => match self.failure_policy {
+                FailurePolicy::Open => {
+                    eprintln!("Store unavailable, forwarding without rate limiting: {}", err);
+                    Ok(Verdict::Allowed { client_id: format!("ip:{}", client_ip) })
+                }
+                FailurePolicy::Closed => Err(err.into()),
+                FailurePolicy::Local => Ok(self.check(&*self.fallback, api_key, client_ip, now).await?),
+            },
+        }
+    }
+
+    /// Takes the client's in-flight slots, applying the failure policy if the store is unreachable.
+    async fn acquire_leases(
+        &self,
+        concurrency: &ConcurrencyLimiter,
+        client_id: &str,
+    ) -> Result<Result<Leases, ConcurrencyRejection>, RateLimitError> {
+        match concurrency.acquire(self.store.clone(), client_id).await {
+            Ok(leases) => Ok(leases),
+            Err(err) => match self.failure_policy {
+                FailurePolicy::Open => {
+                    eprintln!("Store unavailable, forwarding without concurrency limiting: {}", err);
+                    Ok(Ok(Leases::none(self.store.clone())))
+                }
+                FailurePolicy::Closed => Err(err.into()),
+                FailurePolicy::Local => Ok(concurrency.acquire(self.fallback.clone(), client_id).await?),
+            },
+        }
+    }
+
+    /// Increments the client's window counters and checks them against their quotas.
+    async fn check(
+        &self,
+        store: &dyn Store,
+        api_key: Option<&str>,
+        client_ip: &str,
+        now: u64,
+    ) -> Result<Verdict, StoreError> {
+        let tier = self.lookup_tier(store, api_key).await?;
+        let mut plan = self.plan(api_key, tier, client_ip);
+
+        let counters: Vec<Counter> = plan
+            .quotas
+            .iter()
+            .map(|quota| Counter { key: plan.counter_key(quota, now), ttl_secs: quota.window_secs })
+            .collect();
+        let (override_key, hot_key) = (plan.override_key(), hot_clients_key(now));
+        // Counting, the admin override and hot client tracking are issued concurrently
+        let (counts, multiplier, ()) = tokio::try_join!(
+            store.incr(&counters),
+            store.get(&override_key),
+            store.zincr(&hot_key, &plan.id, HOT_CLIENTS_TTL),
+        )?;
+        plan.raise(multiplier);
+
+        Ok(plan.check(counts, now))
+    }
+
+    /// The tier assigned to an API key, from the tiers file or the Redis hash.
+    async fn lookup_tier(&self, store: &dyn Store, api_key: Option<&str>) -> Result<Option<(String, Tier)>, StoreError> {
+        Ok(match (api_key, self.tiers.as_ref().map(|tiers| &tiers.keys)) {
+            (Some(key), Some(KeySource::RedisHash(hash))) => {
+                let tier_name = store.hget(hash, key).await?;
+                tier_name.and_then(|name| self.tier(name))
+            }
+            (Some(key), _) => self.file_tier(key),
+            (None, _) => None,
+        })
+    }
+
+    fn tier(&self, name: String) -> Option<(String, Tier)> {
+        let tier = self.tiers.as_ref()?.tiers.get(&name)?.clone();
+        Some((name, tier))
+    }
+
+    fn file_tier(&self, api_key: &str) -> Option<(String, Tier)> {
+        match &self.tiers.as_ref()?.keys {
+            KeySource::File(keys) => self.tier(keys.get(api_key)?.clone()),
+            KeySource::RedisHash(_) => None,
+        }
+    }
+
+    /// A tier for known API keys, the default limit per IP otherwise.
+    fn plan(&self, api_key: Option<&str>, tier: Option<(String, Tier)>, client_ip: &str) -> ClientPlan {
+        match (api_key, tier) {
+            (Some(key), Some((name, tier))) => ClientPlan {
+                id: format!("key:{}", key),
+                tier: name,
+                quotas: tier.quotas(),
+            },
+            _ => ClientPlan {
+                id: format!("ip:{}", client_ip),
+                tier: "default".to_string(),
+                quotas: vec![Quota { name: "default", window_secs: DEFAULT_WINDOW_SECS, limit: self.request_limit as u64 }],
+            },
+        }
+    }
+}
+
+/// The quotas applying to one client.
+#[derive(Clone)]
+struct ClientPlan {
+    id: String,
+    tier: String,
+    quotas: Vec<Quota>,
+}
+
+impl ClientPlan {
+    /// The client id is a hash tag, keeping all of a client's counters in one cluster slot.
+    fn counter_key(&self, quota: &Quota, now: u64) -> String {
+        format!("rate-limit:{{{}}}:{}:{}", self.id, quota.name, now / quota.window_secs)
+    }
+
+    /// Key of the temporary limit multiplier set through the admin API.
+    fn override_key(&self) -> String {
+        format!("rate-limit:{{{}}}:override", self.id)
+    }
+
+    /// Applies a stored limit multiplier, if any.
+    fn raise(&mut self, multiplier: Option<String>) {
+        if let Some(multiplier) = multiplier.and_then(|m| m.parse::<f64>().ok()) {
+            for quota in &mut self.quotas {
+                quota.limit = (quota.limit as f64 * multiplier) as u64;
+            }
+        }
+    }
+
+    /// Compares the window counts (in quota order) against their limits.
+    fn check(self, counts: Vec<u64>, now: u64) -> Verdict {
+        let ClientPlan { id, tier, quotas } = self;
+        match quotas.into_iter().zip(counts).find(|(quota, count)| *count > quota.limit) {
+            Some((quota, _)) => {
+                let retry_after = quota.window_secs - now % quota.window_secs;
+                Verdict::Rejected(Rejection { client_id: id, tier, quota, retry_after })
+            }
+            None => Verdict::Allowed { client_id: id },
+        }
+    }
+}
+
+/// Outcome of counting a request against the client's quotas.
+enum Verdict {
+    Allowed { client_id: String },
+    Rejected(Rejection),
+}
+
+struct Rejection {
+    client_id: String,
+    tier: String,
+    quota: Quota,
+    retry_after: u64,
+}
+
+impl Rejection {
+    fn response(&self) -> HttpResponse {
+        HttpResponse::TooManyRequests()
+            .insert_header(("retry-after", self.retry_after.to_string()))
+            .json(json!({
+                "error": "Rate limit exceeded. Please try again later.",
+                "tier": self.tier,
+                "quota": self.quota.name,
+                "limit": self.quota.limit,
+                "retry_after": self.retry_after,
+            }))
+    }
+}
+
+/// What to do with a request when Redis can't be reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// Forward the request without rate limiting.
+    Open,
+    /// Reject the request with `503 Service Unavailable`.
+    Closed,
+    /// Rate limit with in-process counters until Redis is back.
+    Local,
+}
+
+impl FromStr for FailurePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(FailurePolicy::Open),
+            "closed" => Ok(FailurePolicy::Closed),
+            "local" => Ok(FailurePolicy::Local),
+            _ => Err(format!("unknown failure policy '{}', expected open, closed or local", s)),
+        }
+    }
+}
+
 #[derive(Debug)]
-struct RateLimitError {
-    inner: reqwest::Error,
+enum RateLimitError {
+    Forward(reqwest::Error),
+    Store(StoreError),
+    Edge(EdgeError),
 }
 
 impl Display for RateLimitError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Forwarding error: {}", self.inner)
+        match self {
+            RateLimitError::Forward(err) => write!(f, "Forwarding error: {}", err),
+            RateLimitError::Store(err) => write!(f, "Rate limit store unavailable: {}", err),
+            RateLimitError::Edge(err) => write!(f, "{}", err),
+        }
     }
 }
 
 impl From<reqwest::Error> for RateLimitError {
     fn from(value: reqwest::Error) -> Self {
-        RateLimitError { inner: value }
+        RateLimitError::Forward(value)
+    }
+}
+
+impl From<StoreError> for RateLimitError {
+    fn from(value: StoreError) -> Self {
+        RateLimitError::Store(value)
+    }
+}
+
+impl From<EdgeError> for RateLimitError {
+    fn from(value: EdgeError) -> Self {
+        RateLimitError::Edge(value)
     }
 }
 
 impl ResponseError for RateLimitError {
     fn status_code(&self) -> actix_web::http::StatusCode {
-        actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+        match self {
+            RateLimitError::Forward(_) => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            RateLimitError::Store(_) => actix_web::http::StatusCode::SERVICE_UNAVAILABLE,
+            RateLimitError::Edge(err) => err.status_code(),
+        }
     }
 
     fn error_response(&self) -> HttpResponse {
+        if let RateLimitError::Edge(err) = self {
+            return err.error_response();
+        }
         HttpResponse::build(self.status_code())
             .insert_header(ContentType::html())
             .body(self.to_string())
@@ -1,6 +1,9 @@
 use dotenv::dotenv;
-use std::env;
-use rate_limiter::RateLimiter;
+use std::{env, time::Duration};
+use rate_limiter::{
+    access::AccessSource, adaptive::AdaptiveConfig, concurrency::ConcurrencyConfig, edge::EdgeLimits, headers::HeaderRules, store::StoreConfig,
+    tiers::TierConfig, FailurePolicy, RateLimiter,
+};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -14,7 +17,26 @@ async fn main() -> std::io::Result<()> {
         .expect("PORT must be a valid u16");
     
     let forward_url = env::var("SERVER_URL").expect("No SERVER_URL provided in env var");
-    let redis_url = env::var("REDIS_URL").expect("No REDIS_URL provided in env var");
+
+    // Where limiter state lives: "redis" (default), "redis-cluster", "redis-sentinel" or "memory"
+    let store = match env::var("STORE").as_deref() {
+        Ok("memory") => StoreConfig::Memory,
+        Ok("redis") | Err(_) => {
+            StoreConfig::Redis(env::var("REDIS_URL").expect("No REDIS_URL provided in env var"))
+        }
+        Ok("redis-cluster") => {
+            let nodes = env::var("REDIS_CLUSTER_NODES").expect("No REDIS_CLUSTER_NODES provided in env var");
+            StoreConfig::RedisCluster(nodes.split(',').map(|node| node.trim().to_string()).collect())
+        }
+        Ok("redis-sentinel") => {
+            let sentinels = env::var("REDIS_SENTINELS").expect("No REDIS_SENTINELS provided in env var");
+            StoreConfig::RedisSentinel {
+                sentinels: sentinels.split(',').map(|sentinel| sentinel.trim().to_string()).collect(),
+                master_name: env::var("REDIS_SENTINEL_MASTER").expect("No REDIS_SENTINEL_MASTER provided in env var"),
+            }
+        }
+        Ok(other) => panic!("Unknown STORE '{}', expected redis, redis-cluster, redis-sentinel or memory", other),
+    };
     
     // Define the request limit
     let request_limit: usize = env::var("RATE_LIMIT")
@@ -23,7 +45,102 @@ async fn main() -> std::io::Result<()> {
         .expect("Rate Limit must be a valid u16");
 
     // Create and run the RateLimiter
-    let rate_limiter = RateLimiter::new(port, forward_url, redis_url, request_limit);
+    let mut rate_limiter = RateLimiter::new(port, forward_url, store.clone(), request_limit);
+
+    // What to do with requests while Redis is down: open, closed or local
+    if let Ok(policy) = env::var("REDIS_FAILURE_POLICY") {
+        let policy: FailurePolicy = policy.parse().expect("REDIS_FAILURE_POLICY must be open, closed or local");
+        rate_limiter = rate_limiter.with_failure_policy(policy);
+    }
+
+    // Optional API-key plans: tiers from a JSON file, key assignments from the file or a Redis hash
+    if let Ok(tiers_file) = env::var("TIERS_FILE") {
+        let key_hash = env::var("API_KEYS_REDIS_HASH").ok();
+        if key_hash.is_some() && matches!(store, StoreConfig::Memory) {
+            panic!("API_KEYS_REDIS_HASH requires a Redis STORE");
+        }
+        let tiers = TierConfig::from_file(&tiers_file, key_hash).expect("Failed to load TIERS_FILE");
+        rate_limiter = rate_limiter.with_tiers(tiers);
+    }
+
+    // Optional allow/deny lists and exempt API keys, from a JSON file or Redis sets
+    let access_source = match (env::var("ACCESS_LISTS_FILE"), env::var("ACCESS_LISTS_REDIS_PREFIX")) {
+        (Ok(path), _) => Some(AccessSource::File(path)),
+        (_, Ok(prefix)) => Some(AccessSource::Redis(prefix)),
+        _ => None,
+    };
+    if matches!(access_source, Some(AccessSource::Redis(_))) && matches!(store, StoreConfig::Memory) {
+        panic!("ACCESS_LISTS_REDIS_PREFIX requires a Redis STORE");
+    }
+    if let Some(source) = access_source {
+        let reload_secs: u64 = env::var("ACCESS_LISTS_RELOAD_SECS")
+            .unwrap_or("30".to_string())
+            .parse()
+            .expect("ACCESS_LISTS_RELOAD_SECS must be a number of seconds");
+        rate_limiter = rate_limiter.with_access_lists(source, Duration::from_secs(reload_secs));
+    }
+
+    // Optional caps on in-flight requests, per client and across all instances
+    let env_number = |name: &str| -> Option<u64> {
+        env::var(name).ok().map(|v| v.parse().unwrap_or_else(|_| panic!("{} must be a number", name)))
+    };
+    let per_client = env_number("MAX_IN_FLIGHT_PER_CLIENT");
+    let global = env_number("MAX_IN_FLIGHT");
+    if per_client.is_some() || global.is_some() {
+        rate_limiter = rate_limiter.with_concurrency_limits(ConcurrencyConfig {
+            per_client,
+            global,
+            max_queued: env_number("MAX_QUEUED").unwrap_or(0) as usize,
+            queue_timeout: Duration::from_millis(env_number("QUEUE_TIMEOUT_MS").unwrap_or(1_000)),
+            lease_ttl: Duration::from_secs(env_number("LEASE_TTL_SECS").unwrap_or(60)),
+        });
+    }
+
+    // Optional global limit adapting to upstream latency and 5xx responses
+    if env::var("ADAPTIVE_LIMIT").is_ok_and(|v| v == "true") {
+        let defaults = AdaptiveConfig::default();
+        rate_limiter = rate_limiter.with_adaptive_limit(AdaptiveConfig {
+            initial_limit: env_number("ADAPTIVE_INITIAL_LIMIT").unwrap_or(defaults.initial_limit),
+            min_limit: env_number("ADAPTIVE_MIN_LIMIT").unwrap_or(defaults.min_limit),
+            max_limit: env_number("ADAPTIVE_MAX_LIMIT").unwrap_or(defaults.max_limit),
+            target_latency: env_number("ADAPTIVE_TARGET_LATENCY_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.target_latency),
+            ..defaults
+        });
+    }
+
+    // Request size and read time limits, and connections per client IP
+    let defaults = EdgeLimits::default();
+    rate_limiter = rate_limiter.with_edge_limits(EdgeLimits {
+        max_header_bytes: env_number("MAX_HEADER_BYTES").map_or(defaults.max_header_bytes, |v| v as usize),
+        max_body_bytes: env_number("MAX_BODY_BYTES").map_or(defaults.max_body_bytes, |v| v as usize),
+        header_read_timeout: env_number("HEADER_READ_TIMEOUT_MS")
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.header_read_timeout),
+        body_read_timeout: env_number("BODY_READ_TIMEOUT_MS")
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.body_read_timeout),
+        max_connections_per_ip: env_number("MAX_CONNECTIONS_PER_IP").map(|v| v as usize),
+    });
+
+    // Optional request and response header rewrites
+    if let Ok(rules_file) = env::var("HEADER_RULES_FILE") {
+        let rules = HeaderRules::from_file(&rules_file).expect("Failed to load HEADER_RULES_FILE");
+        rate_limiter = rate_limiter.with_header_rules(rules);
+    }
+
+    // Optional rejection events for the analytics service, published to Kafka
+    if let Ok(topic) = env::var("REJECTION_EVENTS_TOPIC") {
+        let brokers = env::var("KAFKA_BROKERS").unwrap_or("localhost:9092".to_string());
+        rate_limiter = rate_limiter.with_rejection_events(brokers, topic);
+    }
+
+    // Admin API to inspect and reset clients, only served when a token is set
+    if let Ok(token) = env::var("ADMIN_TOKEN") {
+        rate_limiter = rate_limiter.with_admin_token(token);
+    }
+
     println!("Rate Limiter running on {}", rate_limiter.uri());
     rate_limiter.run().await
 }
@@ -0,0 +1,149 @@
+use std::{sync::Arc, time::{Duration, Instant}};
+use prometheus::{Encoder, HistogramVec, IntCounterVec, IntGauge, Registry, TextEncoder};
+use actix_web::{web::Data, HttpResponse, Responder};
+
+use crate::{
+    store::{Counter, Store, StoreFuture},
+    AppState,
+};
+
+pub struct Metrics {
+    pub registry: Registry,
+    pub adaptive_limit: IntGauge,
+    pub requests_total: IntCounterVec,
+    pub store_duration_seconds: HistogramVec,
+    pub store_errors_total: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("rate_limiter".to_string()), None).unwrap();
+
+        let adaptive_limit = IntGauge::new(
+            "adaptive_limit",
+            "Current adaptive global limit in requests per second",
+        )
+        .unwrap();
+        let requests_total = IntCounterVec::new(
+            prometheus::opts!("requests_total", "Requests allowed or rejected by the rate limiter"),
+            &["decision", "rule", "key_type"],
+        )
+        .unwrap();
+        let store_duration_seconds = HistogramVec::new(
+            prometheus::histogram_opts!(
+                "store_duration_seconds",
+                "Latency of rate limiter store (Redis) operations in seconds",
+                vec![0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0]
+            ),
+            &["operation"],
+        )
+        .unwrap();
+        let store_errors_total = IntCounterVec::new(
+            prometheus::opts!("store_errors_total", "Failed rate limiter store (Redis) operations"),
+            &["operation"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(adaptive_limit.clone())).unwrap();
+        registry.register(Box::new(requests_total.clone())).unwrap();
+        registry.register(Box::new(store_duration_seconds.clone())).unwrap();
+        registry.register(Box::new(store_errors_total.clone())).unwrap();
+
+        Self { registry, adaptive_limit, requests_total, store_duration_seconds, store_errors_total }
+    }
+
+    /// Counts a request. `rule` is the quota, list or cap that decided, `key_type` is `api_key` or `ip`.
+    pub fn record(&self, decision: &str, rule: &str, key_type: &str) {
+        self.requests_total.with_label_values(&[decision, rule, key_type]).inc();
+    }
+
+    pub async fn metrics_handler(state: Data<AppState>) -> impl Responder {
+        if let Some(adaptive) = &state.limits.adaptive {
+            state.limits.metrics.adaptive_limit.set(adaptive.limit() as i64);
+        }
+
+        let mut buffer = Vec::new();
+        let encoder = TextEncoder::new();
+        let metric_families = state.limits.metrics.registry.gather();
+        encoder.encode(&metric_families, &mut buffer).unwrap();
+
+        HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .body(buffer)
+    }
+}
+
+/// Wraps a store to record the latency and errors of every operation.
+pub struct MeteredStore {
+    inner: Arc<dyn Store>,
+    duration: HistogramVec,
+    errors: IntCounterVec,
+}
+
+impl MeteredStore {
+    pub fn new(inner: Arc<dyn Store>, metrics: &Metrics) -> Self {
+        MeteredStore {
+            inner,
+            duration: metrics.store_duration_seconds.clone(),
+            errors: metrics.store_errors_total.clone(),
+        }
+    }
+
+    fn observe<'a, T: 'a>(&'a self, operation: &'static str, future: StoreFuture<'a, T>) -> StoreFuture<'a, T> {
+        Box::pin(async move {
+            let started = Instant::now();
+            let result = future.await;
+            self.duration.with_label_values(&[operation]).observe(started.elapsed().as_secs_f64());
+            if result.is_err() {
+                self.errors.with_label_values(&[operation]).inc();
+            }
+            result
+        })
+    }
+}
+
+impl Store for MeteredStore {
+    fn incr<'a>(&'a self, counters: &'a [Counter]) -> StoreFuture<'a, Vec<u64>> {
+        self.observe("incr", self.inner.incr(counters))
+    }
+
+    fn hget<'a>(&'a self, hash: &'a str, field: &'a str) -> StoreFuture<'a, Option<String>> {
+        self.observe("hget", self.inner.hget(hash, field))
+    }
+
+    fn smembers<'a>(&'a self, set: &'a str) -> StoreFuture<'a, Vec<String>> {
+        self.observe("smembers", self.inner.smembers(set))
+    }
+
+    fn acquire<'a>(&'a self, key: &'a str, lease: &'a str, limit: u64, ttl: Duration) -> StoreFuture<'a, bool> {
+        self.observe("acquire", self.inner.acquire(key, lease, limit, ttl))
+    }
+
+    fn release<'a>(&'a self, key: &'a str, lease: &'a str) -> StoreFuture<'a, ()> {
+        self.observe("release", self.inner.release(key, lease))
+    }
+
+    fn counts<'a>(&'a self, keys: &'a [String]) -> StoreFuture<'a, Vec<u64>> {
+        self.observe("counts", self.inner.counts(keys))
+    }
+
+    fn delete<'a>(&'a self, keys: &'a [String]) -> StoreFuture<'a, ()> {
+        self.observe("delete", self.inner.delete(keys))
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>> {
+        self.observe("get", self.inner.get(key))
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: &'a str, ttl: Duration) -> StoreFuture<'a, ()> {
+        self.observe("set", self.inner.set(key, value, ttl))
+    }
+
+    fn zincr<'a>(&'a self, key: &'a str, member: &'a str, ttl: Duration) -> StoreFuture<'a, ()> {
+        self.observe("zincr", self.inner.zincr(key, member, ttl))
+    }
+
+    fn ztop<'a>(&'a self, key: &'a str, n: usize) -> StoreFuture<'a, Vec<(String, u64)>> {
+        self.observe("ztop", self.inner.ztop(key, n))
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use actix_web::{
+    body::EitherBody,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    HttpResponse,
+};
+use futures::future::{ok, Ready};
+use serde_json::json;
+
+use crate::{access::Access, events::RejectionEvent, request_id::REQUEST_ID_HEADER, Limits, Verdict, API_KEY_HEADER};
+
+/// Rate limiting middleware, started from a [`crate::RateLimitConfig`]:
+///
+/// ```ignore
+/// let rate_limit = RateLimitConfig::new(StoreConfig::Memory, 100).start().await?;
+/// HttpServer::new(move || App::new().wrap(rate_limit.clone()).service(fetch_todos))
+/// ```
+///
+/// Clones share their counters, so one instance can wrap the apps of all workers.
+#[derive(Clone)]
+pub struct RateLimit {
+    limits: Arc<Limits>,
+    record_outcomes: bool,
+}
+
+impl RateLimit {
+    pub(crate) fn new(limits: Arc<Limits>) -> Self {
+        RateLimit { limits, record_outcomes: true }
+    }
+
+    /// Leaves feeding the adaptive limit to the wrapped service, which can time just its upstream
+    /// call rather than the whole request, body upload included.
+    pub(crate) fn without_outcome_recording(mut self) -> Self {
+        self.record_outcomes = false;
+        self
+    }
+
+    pub(crate) fn limits(&self) -> Arc<Limits> {
+        self.limits.clone()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            limits: self.limits.clone(),
+            record_outcomes: self.record_outcomes,
+        })
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    limits: Arc<Limits>,
+    record_outcomes: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let limits = self.limits.clone();
+        let record_outcomes = self.record_outcomes;
+        Box::pin(async move {
+            let peer_ip = req.peer_addr().map(|addr| addr.ip());
+            let client_ip = peer_ip.map(|ip| ip.to_string()).unwrap_or_else(|| "unknown".to_string());
+            let api_key = req.headers().get(API_KEY_HEADER).and_then(|v| v.to_str().ok()).map(str::to_string);
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            // Refined to the client's plan once it is known
+            let mut client_id = match &api_key {
+                Some(key) => format!("key:{}", key),
+                None => format!("ip:{}", client_ip),
+            };
+
+            let limited = match limits.access.current().check(peer_ip, api_key.as_deref()) {
+                Access::Deny => {
+                    let response = HttpResponse::Forbidden().json(json!({ "error": "Access denied." }));
+                    return Ok(reject(&limits, req, "deny_list", &client_id, now, response));
+                }
+                Access::Bypass => false,
+                Access::Limit => match limits.limit(api_key.as_deref(), &client_ip, now).await {
+                    Ok(Verdict::Allowed { client_id: id }) => {
+                        client_id = id;
+                        true
+                    }
+                    Ok(Verdict::Rejected(rejection)) => {
+                        let response = rejection.response();
+                        return Ok(reject(&limits, req, rejection.quota.name, &rejection.client_id, now, response));
+                    }
+                    // Answered here rather than returned, so outer middleware can still add headers
+                    Err(err) => return Ok(req.error_response(err).map_into_right_body()),
+                },
+            };
+
+            // In-flight slots are held until the wrapped service has responded
+            let _leases = match &limits.concurrency {
+                Some(concurrency) if limited => match limits.acquire_leases(concurrency, &client_id).await {
+                    Ok(Ok(leases)) => Some(leases),
+                    Ok(Err(rejection)) => {
+                        return Ok(reject(&limits, req, rejection.rule(), &client_id, now, rejection.response()));
+                    }
+                    Err(err) => return Ok(req.error_response(err).map_into_right_body()),
+                },
+                _ => None,
+            };
+
+            // The adaptive limit protects the upstream as a whole, after per-client limits
+            if let Some(adaptive) = &limits.adaptive {
+                if !adaptive.try_admit() {
+                    let response = HttpResponse::ServiceUnavailable().insert_header(("retry-after", "1")).json(json!({
+                        "error": "Upstream is overloaded. Please try again later.",
+                        "limit": adaptive.limit(),
+                    }));
+                    return Ok(reject(&limits, req, "adaptive", &client_id, now, response));
+                }
+            }
+
+            let rule = if limited { "quota" } else { "allow_list" };
+            limits.metrics.record("allowed", rule, key_type(&client_id));
+
+            let started = Instant::now();
+            let result = service.call(req).await;
+            if let Some(adaptive) = limits.adaptive.as_ref().filter(|_| record_outcomes) {
+                let failed = result.as_ref().map_or(true, |response| response.status().is_server_error());
+                adaptive.record(started.elapsed(), failed);
+            }
+            result.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+fn key_type(client_id: &str) -> &'static str {
+    if client_id.starts_with("key:") {
+        "api_key"
+    } else {
+        "ip"
+    }
+}
+
+/// Answers a rejected request with `response`, counting it and publishing its event.
+fn reject<B>(
+    limits: &Limits,
+    req: ServiceRequest,
+    rule: &str,
+    client_id: &str,
+    now: u64,
+    response: HttpResponse,
+) -> ServiceResponse<EitherBody<B>> {
+    limits.metrics.record("rejected", rule, key_type(client_id));
+    if let Some(events) = &limits.events {
+        events.publish(RejectionEvent {
+            request_id: req.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string(),
+            client_id: client_id.to_string(),
+            key_type: key_type(client_id),
+            rule: rule.to_string(),
+            method: req.method().to_string(),
+            path: req.path().to_string(),
+            timestamp: now,
+        });
+    }
+    req.into_response(response).map_into_right_body()
+}
@@ -0,0 +1,177 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::{Counter, Store, StoreFuture};
+
+const SHARDS: usize = 16;
+
+/// Number of entries a shard may hold before expired ones are swept.
+const SWEEP_THRESHOLD: usize = 1_024;
+
+struct Entry {
+    value: u64,
+    expires_at: Instant,
+}
+
+/// Member scores and expiry of a sorted set.
+type SortedSet = (HashMap<String, u64>, Instant);
+
+#[derive(Default)]
+struct Shard {
+    entries: HashMap<String, Entry>,
+    // Size after the last sweep, so sweeps stay amortized while the shard is full of live keys
+    swept_len: usize,
+}
+
+/// In-process store, sharded by key to keep lock contention between workers low.
+///
+/// Expired counters are dropped lazily: on access, and in a sweep of the whole shard
+/// whenever it has grown past [`SWEEP_THRESHOLD`] entries since the previous sweep. State
+/// is local to this instance, so limits aren't shared between several rate limiters.
+pub struct MemoryStore {
+    shards: Vec<Mutex<Shard>>,
+    // lease set -> lease -> expiry
+    leases: Mutex<HashMap<String, HashMap<String, Instant>>>,
+    values: Mutex<HashMap<String, (String, Instant)>>,
+    scores: Mutex<HashMap<String, SortedSet>>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        MemoryStore {
+            shards: (0..SHARDS).map(|_| Mutex::default()).collect(),
+            leases: Mutex::default(),
+            values: Mutex::default(),
+            scores: Mutex::default(),
+        }
+    }
+}
+
+impl MemoryStore {
+    fn shard(&self, key: &str) -> &Mutex<Shard> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % SHARDS]
+    }
+
+    fn incr_one(&self, counter: &Counter, now: Instant) -> u64 {
+        let mut shard = self.shard(&counter.key).lock().unwrap();
+        if shard.entries.len() >= shard.swept_len + SWEEP_THRESHOLD {
+            shard.entries.retain(|_, entry| entry.expires_at > now);
+            shard.swept_len = shard.entries.len();
+        }
+
+        let expires_at = now + Duration::from_secs(counter.ttl_secs);
+        let entry = shard.entries.entry(counter.key.clone()).or_insert(Entry { value: 0, expires_at });
+        if entry.expires_at <= now {
+            entry.value = 0;
+        }
+        // Like Redis INCR + EXPIRE, every increment pushes the expiry back
+        entry.value += 1;
+        entry.expires_at = expires_at;
+        entry.value
+    }
+}
+
+impl Store for MemoryStore {
+    fn incr<'a>(&'a self, counters: &'a [Counter]) -> StoreFuture<'a, Vec<u64>> {
+        let now = Instant::now();
+        let values = counters.iter().map(|counter| self.incr_one(counter, now)).collect();
+        Box::pin(async move { Ok(values) })
+    }
+
+    /// API keys can't be assigned in memory, so lookups never find a tier.
+    fn hget<'a>(&'a self, _hash: &'a str, _field: &'a str) -> StoreFuture<'a, Option<String>> {
+        Box::pin(async move { Ok(None) })
+    }
+
+    /// Sets can't be filled in memory either, so they are always empty.
+    fn smembers<'a>(&'a self, _set: &'a str) -> StoreFuture<'a, Vec<String>> {
+        Box::pin(async move { Ok(Vec::new()) })
+    }
+
+    fn acquire<'a>(&'a self, key: &'a str, lease: &'a str, limit: u64, ttl: Duration) -> StoreFuture<'a, bool> {
+        let now = Instant::now();
+        let mut leases = self.leases.lock().unwrap();
+        let held = leases.entry(key.to_string()).or_default();
+        held.retain(|_, expires_at| *expires_at > now);
+        let granted = (held.len() as u64) < limit;
+        if granted {
+            held.insert(lease.to_string(), now + ttl);
+        }
+        Box::pin(async move { Ok(granted) })
+    }
+
+    fn release<'a>(&'a self, key: &'a str, lease: &'a str) -> StoreFuture<'a, ()> {
+        let mut leases = self.leases.lock().unwrap();
+        if let Some(held) = leases.get_mut(key) {
+            held.remove(lease);
+            if held.is_empty() {
+                leases.remove(key);
+            }
+        }
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn counts<'a>(&'a self, keys: &'a [String]) -> StoreFuture<'a, Vec<u64>> {
+        let now = Instant::now();
+        let counts = keys
+            .iter()
+            .map(|key| match self.shard(key).lock().unwrap().entries.get(key) {
+                Some(entry) if entry.expires_at > now => entry.value,
+                _ => 0,
+            })
+            .collect();
+        Box::pin(async move { Ok(counts) })
+    }
+
+    fn delete<'a>(&'a self, keys: &'a [String]) -> StoreFuture<'a, ()> {
+        for key in keys {
+            self.shard(key).lock().unwrap().entries.remove(key);
+            self.values.lock().unwrap().remove(key);
+        }
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>> {
+        let value = match self.values.lock().unwrap().get(key) {
+            Some((value, expires_at)) if *expires_at > Instant::now() => Some(value.clone()),
+            _ => None,
+        };
+        Box::pin(async move { Ok(value) })
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: &'a str, ttl: Duration) -> StoreFuture<'a, ()> {
+        let now = Instant::now();
+        let mut values = self.values.lock().unwrap();
+        values.retain(|_, (_, expires_at)| *expires_at > now);
+        values.insert(key.to_string(), (value.to_string(), now + ttl));
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn zincr<'a>(&'a self, key: &'a str, member: &'a str, ttl: Duration) -> StoreFuture<'a, ()> {
+        let now = Instant::now();
+        let mut scores = self.scores.lock().unwrap();
+        scores.retain(|_, (_, expires_at)| *expires_at > now);
+        let (members, expires_at) = scores.entry(key.to_string()).or_insert_with(|| (HashMap::new(), now));
+        *members.entry(member.to_string()).or_default() += 1;
+        *expires_at = now + ttl;
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn ztop<'a>(&'a self, key: &'a str, n: usize) -> StoreFuture<'a, Vec<(String, u64)>> {
+        let mut top: Vec<(String, u64)> = match self.scores.lock().unwrap().get(key) {
+            Some((members, expires_at)) if *expires_at > Instant::now() => {
+                members.iter().map(|(member, score)| (member.clone(), *score)).collect()
+            }
+            _ => Vec::new(),
+        };
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(n);
+        Box::pin(async move { Ok(top) })
+    }
+}
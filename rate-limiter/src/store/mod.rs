@@ -0,0 +1,102 @@
+use std::{fmt::Display, future::Future, pin::Pin, sync::Arc, time::Duration};
+use ::redis::RedisError;
+
+mod memory;
+pub use memory::MemoryStore;
+
+mod redis;
+pub use self::redis::RedisStore;
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, StoreError>> + Send + 'a>>;
+
+/// A window counter to increment, expiring `ttl_secs` after it is created.
+pub struct Counter {
+    pub key: String,
+    pub ttl_secs: u64,
+}
+
+/// Backend holding the rate limiter's state.
+pub trait Store: Send + Sync {
+    /// Increments every counter by one and returns the new values, in order.
+    fn incr<'a>(&'a self, counters: &'a [Counter]) -> StoreFuture<'a, Vec<u64>>;
+
+    /// Reads a field of a hash, used to resolve API keys assigned in Redis.
+    fn hget<'a>(&'a self, hash: &'a str, field: &'a str) -> StoreFuture<'a, Option<String>>;
+
+    /// Reads all members of a set, used to load access lists kept in Redis.
+    fn smembers<'a>(&'a self, set: &'a str) -> StoreFuture<'a, Vec<String>>;
+
+    /// Adds `lease` to the leases held on `key` if fewer than `limit` unexpired ones are held.
+    /// Returns whether the lease was granted; it expires after `ttl` unless released.
+    fn acquire<'a>(&'a self, key: &'a str, lease: &'a str, limit: u64, ttl: Duration) -> StoreFuture<'a, bool>;
+
+    /// Gives back a lease granted by [`Store::acquire`].
+    fn release<'a>(&'a self, key: &'a str, lease: &'a str) -> StoreFuture<'a, ()>;
+
+    /// Reads counters without incrementing them; missing counters read as zero.
+    fn counts<'a>(&'a self, keys: &'a [String]) -> StoreFuture<'a, Vec<u64>>;
+
+    /// Deletes counters and values.
+    fn delete<'a>(&'a self, keys: &'a [String]) -> StoreFuture<'a, ()>;
+
+    fn get<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>>;
+
+    /// Stores a value expiring after `ttl`.
+    fn set<'a>(&'a self, key: &'a str, value: &'a str, ttl: Duration) -> StoreFuture<'a, ()>;
+
+    /// Adds one to the score of `member` in a sorted set expiring after `ttl`.
+    fn zincr<'a>(&'a self, key: &'a str, member: &'a str, ttl: Duration) -> StoreFuture<'a, ()>;
+
+    /// The `n` members with the highest scores, highest first.
+    fn ztop<'a>(&'a self, key: &'a str, n: usize) -> StoreFuture<'a, Vec<(String, u64)>>;
+}
+
+/// Which store backend to run with.
+#[derive(Debug, Clone)]
+pub enum StoreConfig {
+    /// Shared state in Redis at the given URL.
+    Redis(String),
+    /// Shared state in a Redis Cluster, reached through these seed node URLs.
+    RedisCluster(Vec<String>),
+    /// Shared state in the Redis primary named `master_name`, discovered through Sentinel.
+    RedisSentinel { sentinels: Vec<String>, master_name: String },
+    /// In-process state, for a single instance or tests.
+    Memory,
+}
+
+impl StoreConfig {
+    pub fn build(&self) -> Arc<dyn Store> {
+        match self {
+            StoreConfig::Redis(url) => {
+                let client = ::redis::Client::open(url.clone()).expect("Invalid Redis URL");
+                Arc::new(RedisStore::new(client))
+            }
+            StoreConfig::RedisCluster(nodes) => {
+                Arc::new(RedisStore::cluster(nodes.clone()).expect("Invalid Redis Cluster node URL"))
+            }
+            StoreConfig::RedisSentinel { sentinels, master_name } => Arc::new(
+                RedisStore::sentinel(sentinels.clone(), master_name.clone()).expect("Invalid Redis Sentinel URL"),
+            ),
+            StoreConfig::Memory => Arc::new(MemoryStore::default()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Redis(RedisError),
+}
+
+impl Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreError::Redis(err) => write!(f, "Redis error: {}", err),
+        }
+    }
+}
+
+impl From<RedisError> for StoreError {
+    fn from(value: RedisError) -> Self {
+        StoreError::Redis(value)
+    }
+}
@@ -0,0 +1,202 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use redis::{
+    aio::{ConnectionManager, ConnectionManagerConfig, MultiplexedConnection},
+    cluster::ClusterClient,
+    cluster_async::ClusterConnection,
+    sentinel::{SentinelClient, SentinelServerType},
+    AsyncConnectionConfig, Client as RedisClient, FromRedisValue, Pipeline, RedisError,
+};
+use tokio::sync::{Mutex, OnceCell};
+
+use super::{Counter, Store, StoreFuture};
+
+/// Drops expired leases, then grants the new one if fewer than the limit remain.
+/// KEYS[1] = lease set, ARGV = now (ms), limit, lease expiry (ms), lease id, ttl (ms)
+const ACQUIRE_LEASE: &str = r"
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', ARGV[1])
+if redis.call('ZCARD', KEYS[1]) < tonumber(ARGV[2]) then
+    redis.call('ZADD', KEYS[1], ARGV[3], ARGV[4])
+    redis.call('PEXPIRE', KEYS[1], ARGV[5])
+    return 1
+end
+return 0
+";
+
+/// Timeout for connecting to Redis and for each command, so an outage hits the failure
+/// policy quickly instead of stalling requests.
+const REDIS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Store backed by Redis, shared by all actix workers without locking.
+///
+/// Connections are opened on first use, so the rate limiter can start while Redis is down.
+/// All counters of a client share a hash tag, so each request's pipeline targets a single
+/// slot and works unchanged against a cluster.
+pub struct RedisStore {
+    backend: Backend,
+}
+
+enum Backend {
+    /// [`ConnectionManager`] reconnects in the background whenever the connection drops.
+    Single { client: RedisClient, manager: OnceCell<ConnectionManager> },
+    /// The cluster connection follows slot migrations and reconnects to nodes by itself.
+    Cluster { client: ClusterClient, connection: OnceCell<ClusterConnection> },
+    /// The primary is looked up through the sentinels and forgotten on the first error,
+    /// so the next request follows a failover to the newly promoted primary.
+    Sentinel { client: Mutex<SentinelClient>, connection: Mutex<Option<MultiplexedConnection>> },
+}
+
+impl RedisStore {
+    pub fn new(client: RedisClient) -> Self {
+        RedisStore { backend: Backend::Single { client, manager: OnceCell::new() } }
+    }
+
+    /// Connects to a Redis Cluster through any of the `nodes` seed URLs.
+    pub fn cluster(nodes: Vec<String>) -> Result<Self, RedisError> {
+        let client = ClusterClient::builder(nodes)
+            .connection_timeout(REDIS_TIMEOUT)
+            .response_timeout(REDIS_TIMEOUT)
+            .retries(1)
+            .build()?;
+        Ok(RedisStore { backend: Backend::Cluster { client, connection: OnceCell::new() } })
+    }
+
+    /// Connects to the primary of `master_name` as reported by the `sentinels`.
+    pub fn sentinel(sentinels: Vec<String>, master_name: String) -> Result<Self, RedisError> {
+        let client = SentinelClient::build(sentinels, master_name, None, SentinelServerType::Master)?;
+        Ok(RedisStore {
+            backend: Backend::Sentinel { client: Mutex::new(client), connection: Mutex::new(None) },
+        })
+    }
+
+    async fn query<T: FromRedisValue>(&self, pipe: &Pipeline) -> Result<T, RedisError> {
+        match &self.backend {
+            Backend::Single { client, manager } => {
+                let mut con = manager
+                    .get_or_try_init(|| {
+                        let config = ConnectionManagerConfig::new()
+                            .set_connection_timeout(REDIS_TIMEOUT)
+                            .set_response_timeout(REDIS_TIMEOUT)
+                            .set_number_of_retries(1);
+                        ConnectionManager::new_with_config(client.clone(), config)
+                    })
+                    .await?
+                    .clone();
+                pipe.query_async(&mut con).await
+            }
+            Backend::Cluster { client, connection } => {
+                let mut con = connection.get_or_try_init(|| client.get_async_connection()).await?.clone();
+                pipe.query_async(&mut con).await
+            }
+            Backend::Sentinel { client, connection } => {
+                let cached = connection.lock().await.clone();
+                let mut con = match cached {
+                    Some(con) => con,
+                    None => {
+                        let config = AsyncConnectionConfig::new()
+                            .set_connection_timeout(REDIS_TIMEOUT)
+                            .set_response_timeout(REDIS_TIMEOUT);
+                        let con = client.lock().await.get_async_connection_with_config(&config).await?;
+                        *connection.lock().await = Some(con.clone());
+                        con
+                    }
+                };
+                let result = pipe.query_async(&mut con).await;
+                if result.is_err() {
+                    *connection.lock().await = None;
+                }
+                result
+            }
+        }
+    }
+}
+
+impl Store for RedisStore {
+    fn incr<'a>(&'a self, counters: &'a [Counter]) -> StoreFuture<'a, Vec<u64>> {
+        Box::pin(async move {
+            let mut pipe = redis::pipe();
+            for counter in counters {
+                pipe.incr(&counter.key, 1).expire(&counter.key, counter.ttl_secs as i64).ignore();
+            }
+            Ok(self.query(&pipe).await?)
+        })
+    }
+
+    fn hget<'a>(&'a self, hash: &'a str, field: &'a str) -> StoreFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let (value,) = self.query(redis::pipe().hget(hash, field)).await?;
+            Ok(value)
+        })
+    }
+
+    fn smembers<'a>(&'a self, set: &'a str) -> StoreFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            let (members,) = self.query(redis::pipe().smembers(set)).await?;
+            Ok(members)
+        })
+    }
+
+    fn acquire<'a>(&'a self, key: &'a str, lease: &'a str, limit: u64, ttl: Duration) -> StoreFuture<'a, bool> {
+        Box::pin(async move {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+            let ttl = ttl.as_millis() as u64;
+            let mut pipe = redis::pipe();
+            pipe.cmd("EVAL").arg(ACQUIRE_LEASE).arg(1).arg(key).arg(now).arg(limit).arg(now + ttl).arg(lease).arg(ttl);
+            let (granted,): (bool,) = self.query(&pipe).await?;
+            Ok(granted)
+        })
+    }
+
+    fn release<'a>(&'a self, key: &'a str, lease: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let _: (u64,) = self.query(redis::pipe().zrem(key, lease)).await?;
+            Ok(())
+        })
+    }
+
+    fn counts<'a>(&'a self, keys: &'a [String]) -> StoreFuture<'a, Vec<u64>> {
+        Box::pin(async move {
+            let mut pipe = redis::pipe();
+            for key in keys {
+                pipe.get(key);
+            }
+            let counts: Vec<Option<u64>> = self.query(&pipe).await?;
+            Ok(counts.into_iter().map(Option::unwrap_or_default).collect())
+        })
+    }
+
+    fn delete<'a>(&'a self, keys: &'a [String]) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let mut pipe = redis::pipe();
+            for key in keys {
+                pipe.del(key).ignore();
+            }
+            Ok(self.query(&pipe).await?)
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let (value,) = self.query(redis::pipe().get(key)).await?;
+            Ok(value)
+        })
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: &'a str, ttl: Duration) -> StoreFuture<'a, ()> {
+        Box::pin(async move { Ok(self.query(redis::pipe().set_ex(key, value, ttl.as_secs()).ignore()).await?) })
+    }
+
+    fn zincr<'a>(&'a self, key: &'a str, member: &'a str, ttl: Duration) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let mut pipe = redis::pipe();
+            pipe.zincr(key, member, 1).ignore().expire(key, ttl.as_secs() as i64).ignore();
+            Ok(self.query(&pipe).await?)
+        })
+    }
+
+    fn ztop<'a>(&'a self, key: &'a str, n: usize) -> StoreFuture<'a, Vec<(String, u64)>> {
+        Box::pin(async move {
+            let (top,) = self.query(redis::pipe().zrevrange_withscores(key, 0, n as isize - 1)).await?;
+            Ok(top)
+        })
+    }
+}
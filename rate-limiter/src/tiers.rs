@@ -0,0 +1,79 @@
+use std::{collections::HashMap, fs, io};
+use serde::{Deserialize, Serialize};
+
+/// A single fixed-window quota, e.g. 60 requests per minute.
+#[derive(Debug, Clone, Serialize)]
+pub struct Quota {
+    pub name: &'static str,
+    pub window_secs: u64,
+    pub limit: u64,
+}
+
+/// Per-second, per-minute and per-day quotas of a plan. A missing value means unlimited.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Tier {
+    pub per_second: Option<u64>,
+    pub per_minute: Option<u64>,
+    pub per_day: Option<u64>,
+}
+
+impl Tier {
+    /// Quotas enforced for this tier, shortest window first.
+    pub fn quotas(&self) -> Vec<Quota> {
+        [
+            ("per_second", 1, self.per_second),
+            ("per_minute", 60, self.per_minute),
+            ("per_day", 86_400, self.per_day),
+        ]
+        .into_iter()
+        .filter_map(|(name, window_secs, limit)| limit.map(|limit| Quota { name, window_secs, limit }))
+        .collect()
+    }
+}
+
+/// Where the API key → tier mapping is looked up.
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    /// Mapping loaded once from the `keys` section of the tiers file.
+    File(HashMap<String, String>),
+    /// Mapping read on every request from a Redis hash (`HGET <hash> <api key>`).
+    RedisHash(String),
+}
+
+/// Plans (free, pro, internal...) and the API keys assigned to them.
+#[derive(Debug, Clone)]
+pub struct TierConfig {
+    pub tiers: HashMap<String, Tier>,
+    pub keys: KeySource,
+}
+
+#[derive(Deserialize)]
+struct TierFile {
+    tiers: HashMap<String, Tier>,
+    #[serde(default)]
+    keys: HashMap<String, String>,
+}
+
+impl TierConfig {
+    /// Loads tiers and key assignments from a JSON file:
+    ///
+    /// ```json
+    /// {
+    ///   "tiers": { "free": { "per_second": 2, "per_minute": 60, "per_day": 1000 } },
+    ///   "keys": { "my-api-key": "free" }
+    /// }
+    /// ```
+    ///
+    /// When `redis_hash` is set, keys are resolved from that Redis hash instead of the file.
+    pub fn from_file(path: &str, redis_hash: Option<String>) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let file: TierFile = serde_json::from_str(&content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let keys = match redis_hash {
+            Some(hash) => KeySource::RedisHash(hash),
+            None => KeySource::File(file.keys),
+        };
+        Ok(TierConfig { tiers: file.tiers, keys })
+    }
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{free_port, get_status, start, upstream};
+use rate_limiter::{access::AccessSource, store::StoreConfig, RateLimiter, API_KEY_HEADER};
+use wiremock::MockServer;
+
+/// Writes an access list file unique to this test and returns its path.
+fn access_file(name: &str, content: &str) -> String {
+    let path = std::env::temp_dir().join(format!("rate-limiter-test-access-{}-{}.json", name, free_port()));
+    std::fs::write(&path, content).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+/// Starts a rate limiter allowing one request per window, returning its URI and upstream.
+async fn start_with(path: &str, reload_every: Duration) -> (String, MockServer) {
+    let upstream = upstream().await;
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 1)
+        .with_access_lists(AccessSource::File(path.to_string()), reload_every);
+    (start(limiter).await, upstream)
+}
+
+#[actix_web::test]
+async fn allowed_cidr_bypasses_limits() {
+    let path = access_file("allow", r#"{ "allow": ["127.0.0.0/8"] }"#);
+    let (uri, _upstream) = start_with(&path, Duration::from_secs(60)).await;
+
+    for _ in 0..5 {
+        assert_eq!(get_status(&uri).await, 200);
+    }
+}
+
+#[actix_web::test]
+async fn denied_ip_gets_403() {
+    let path = access_file("deny", r#"{ "allow": ["127.0.0.0/8"], "deny": ["127.0.0.1"] }"#);
+    let (uri, _upstream) = start_with(&path, Duration::from_secs(60)).await;
+
+    assert_eq!(get_status(&uri).await, 403);
+}
+
+#[actix_web::test]
+async fn exempt_api_key_bypasses_limits() {
+    let path = access_file("exempt", r#"{ "exempt_keys": ["ci-key"] }"#);
+    let (uri, _upstream) = start_with(&path, Duration::from_secs(60)).await;
+
+    let client = reqwest::Client::new();
+    for _ in 0..5 {
+        let response = client.get(format!("{}/todos", uri)).header(API_KEY_HEADER, "ci-key").send().await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+}
+
+#[actix_web::test]
+async fn lists_are_reloaded_at_runtime() {
+    let path = access_file("reload", r#"{ "allow": ["127.0.0.1"] }"#);
+    let (uri, _upstream) = start_with(&path, Duration::from_millis(100)).await;
+    assert_eq!(get_status(&uri).await, 200);
+
+    std::fs::write(&path, r#"{ "deny": ["127.0.0.1"] }"#).unwrap();
+    actix_web::rt::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(get_status(&uri).await, 403);
+}
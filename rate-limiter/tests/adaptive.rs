@@ -0,0 +1,97 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{free_port, get_status, start, upstream};
+use rate_limiter::{adaptive::AdaptiveConfig, store::StoreConfig, RateLimiter};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use wiremock::{matchers::any, Mock, MockServer, ResponseTemplate};
+
+fn config(initial_limit: u64) -> AdaptiveConfig {
+    AdaptiveConfig {
+        initial_limit,
+        min_limit: 1,
+        max_limit: 1_000,
+        increase: 1,
+        decrease_factor: 0.5,
+        interval: Duration::from_secs(1),
+        ..Default::default()
+    }
+}
+
+async fn start_with(upstream: &MockServer, config: AdaptiveConfig) -> String {
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 1_000).with_adaptive_limit(config);
+    start(limiter).await
+}
+
+async fn current_limit(uri: &str) -> u64 {
+    let metrics = reqwest::get(format!("{}/rate-limiter/metrics", uri)).await.unwrap().text().await.unwrap();
+    let line = metrics.lines().find(|line| line.starts_with("rate_limiter_adaptive_limit ")).unwrap();
+    line.split(' ').nth(1).unwrap().parse().unwrap()
+}
+
+#[actix_web::test]
+async fn rejects_above_the_limit_with_503() {
+    let upstream = upstream().await;
+    let uri = start_with(&upstream, AdaptiveConfig { min_limit: 2, max_limit: 2, ..config(2) }).await;
+
+    common::wait_for_fresh_window(1).await;
+    assert_eq!(get_status(&uri).await, 200);
+    assert_eq!(get_status(&uri).await, 200);
+    assert_eq!(get_status(&uri).await, 503);
+}
+
+#[actix_web::test]
+async fn shrinks_when_upstream_fails() {
+    let upstream = MockServer::start().await;
+    Mock::given(any()).respond_with(ResponseTemplate::new(500)).mount(&upstream).await;
+    // Long enough for all failures to land in the first interval
+    let uri = start_with(&upstream, AdaptiveConfig { interval: Duration::from_secs(3), ..config(100) }).await;
+
+    for _ in 0..5 {
+        assert_eq!(get_status(&uri).await, 500);
+    }
+    actix_web::rt::time::sleep(Duration::from_millis(3_100)).await;
+    get_status(&uri).await;
+
+    assert_eq!(current_limit(&uri).await, 50);
+}
+
+#[actix_web::test]
+async fn grows_while_healthy_and_in_use() {
+    let upstream = upstream().await;
+    let uri = start_with(&upstream, config(2)).await;
+
+    common::wait_for_fresh_window(1).await;
+    get_status(&uri).await;
+    get_status(&uri).await;
+    actix_web::rt::time::sleep(Duration::from_millis(1_100)).await;
+    get_status(&uri).await;
+
+    assert_eq!(current_limit(&uri).await, 3);
+}
+
+#[actix_web::test]
+async fn slow_request_bodies_do_not_count_as_upstream_latency() {
+    let upstream = upstream().await;
+    let uri = start_with(&upstream, AdaptiveConfig { target_latency: Duration::from_millis(200), ..config(100) }).await;
+
+    // The body trickles in over 600ms, while the upstream answers at once
+    let mut stream = TcpStream::connect(uri.trim_start_matches("http://")).await.unwrap();
+    stream.write_all(b"POST /todo HTTP/1.1\r\nhost: localhost\r\ncontent-length: 3\r\n\r\n").await.unwrap();
+    for byte in [b"x", b"y", b"z"] {
+        actix_web::rt::time::sleep(Duration::from_millis(300)).await;
+        stream.write_all(byte).await.unwrap();
+    }
+    let mut response = vec![0; 1024];
+    let read = stream.read(&mut response).await.unwrap();
+    assert!(response[..read].starts_with(b"HTTP/1.1 200"));
+
+    actix_web::rt::time::sleep(Duration::from_millis(1_100)).await;
+    get_status(&uri).await;
+
+    assert_eq!(current_limit(&uri).await, 100);
+}
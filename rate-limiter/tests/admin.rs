@@ -0,0 +1,97 @@
+mod common;
+
+use common::{free_port, get_status, start, upstream, wait_for_fresh_window};
+use rate_limiter::{store::StoreConfig, RateLimiter};
+use serde_json::{json, Value};
+use wiremock::MockServer;
+
+const TOKEN: &str = "secret";
+const CLIENT: &str = "ip:127.0.0.1";
+
+/// Starts a rate limiter allowing two requests per window, with the admin API enabled.
+async fn start_with_admin() -> (String, MockServer) {
+    let upstream = upstream().await;
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 2).with_admin_token(TOKEN.to_string());
+    let uri = start(limiter).await;
+    wait_for_fresh_window(10).await;
+    (uri, upstream)
+}
+
+fn admin(method: reqwest::Method, url: String) -> reqwest::RequestBuilder {
+    reqwest::Client::new().request(method, url).bearer_auth(TOKEN)
+}
+
+#[actix_web::test]
+async fn rejects_requests_without_the_token() {
+    let (uri, _upstream) = start_with_admin().await;
+
+    let response = reqwest::get(format!("{}/admin/clients/{}", uri, CLIENT)).await.unwrap();
+    assert_eq!(response.status(), 401);
+}
+
+#[actix_web::test]
+async fn inspects_a_client() {
+    let (uri, _upstream) = start_with_admin().await;
+    get_status(&uri).await;
+
+    let response = admin(reqwest::Method::GET, format!("{}/admin/clients/{}", uri, CLIENT)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = serde_json::from_slice(&response.bytes().await.unwrap()).unwrap();
+    assert_eq!(body["tier"], "default");
+    assert_eq!(body["quotas"][0]["count"], 1);
+    assert_eq!(body["quotas"][0]["remaining"], 1);
+}
+
+#[actix_web::test]
+async fn resets_a_client() {
+    let (uri, _upstream) = start_with_admin().await;
+    for _ in 0..3 {
+        get_status(&uri).await;
+    }
+    assert_eq!(get_status(&uri).await, 429);
+
+    let response = admin(reqwest::Method::DELETE, format!("{}/admin/clients/{}", uri, CLIENT)).send().await.unwrap();
+    assert_eq!(response.status(), 204);
+    assert_eq!(get_status(&uri).await, 200);
+}
+
+#[actix_web::test]
+async fn temporarily_raises_a_limit() {
+    let (uri, _upstream) = start_with_admin().await;
+
+    let response = admin(reqwest::Method::PUT, format!("{}/admin/clients/{}/override", uri, CLIENT))
+        .header("content-type", "application/json")
+        .body(json!({ "multiplier": 2.0, "ttl_secs": 60 }).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+
+    for _ in 0..4 {
+        assert_eq!(get_status(&uri).await, 200);
+    }
+    assert_eq!(get_status(&uri).await, 429);
+}
+
+#[actix_web::test]
+async fn lists_hot_clients() {
+    let (uri, _upstream) = start_with_admin().await;
+    get_status(&uri).await;
+    get_status(&uri).await;
+
+    let response = admin(reqwest::Method::GET, format!("{}/admin/hot?n=5", uri)).send().await.unwrap();
+    let body: Value = serde_json::from_slice(&response.bytes().await.unwrap()).unwrap();
+    assert_eq!(body[0]["client_id"], CLIENT);
+    assert!(body[0]["requests"].as_u64().unwrap() >= 2);
+}
+
+#[actix_web::test]
+async fn rejects_hot_client_counts_out_of_range() {
+    let (uri, _upstream) = start_with_admin().await;
+    get_status(&uri).await;
+
+    for n in [0, 101] {
+        let response = admin(reqwest::Method::GET, format!("{}/admin/hot?n={}", uri, n)).send().await.unwrap();
+        assert_eq!(response.status(), 400);
+    }
+}
@@ -0,0 +1,85 @@
+#![allow(dead_code)]
+
+use std::{
+    net::TcpListener,
+    process::{Child, Command, Stdio},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rate_limiter::RateLimiter;
+use wiremock::{matchers::any, Mock, MockServer, ResponseTemplate};
+
+pub fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+/// An upstream answering `200 upstream` to everything.
+pub async fn upstream() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200).set_body_string("upstream"))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Starts the rate limiter in the background and waits until it accepts connections.
+///
+/// Readiness is probed with TCP connects only, so no request is counted against any limit.
+pub async fn start(rate_limiter: RateLimiter) -> String {
+    let uri = rate_limiter.uri();
+    let addr = uri.trim_start_matches("http://").to_string();
+    actix_web::rt::spawn(async move { rate_limiter.run().await });
+
+    for _ in 0..50 {
+        if actix_web::rt::net::TcpStream::connect(&addr).await.is_ok() {
+            return uri;
+        }
+        actix_web::rt::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("rate limiter did not start on {}", uri);
+}
+
+pub async fn get_status(uri: &str) -> u16 {
+    reqwest::get(format!("{}/todos", uri)).await.unwrap().status().as_u16()
+}
+
+/// Waits for a fixed window of `window_secs` to start if the current one is about to end,
+/// so the requests of a test are counted in the same window.
+pub async fn wait_for_fresh_window(window_secs: u64) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let elapsed = Duration::from_millis((now.as_millis() % (window_secs as u128 * 1_000)) as u64);
+    let remaining = Duration::from_secs(window_secs) - elapsed;
+    if remaining < Duration::from_secs(1) || window_secs == 1 {
+        actix_web::rt::time::sleep(remaining).await;
+    }
+}
+
+/// A `redis-server` process, killed on drop.
+pub struct RedisServer(Child);
+
+impl From<Child> for RedisServer {
+    fn from(child: Child) -> Self {
+        RedisServer(child)
+    }
+}
+
+impl Drop for RedisServer {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Spawns a throwaway `redis-server` with extra `args`, or returns `None` if it isn't installed.
+pub fn spawn_redis(port: u16, args: &[&str]) -> Option<RedisServer> {
+    let child = Command::new("redis-server")
+        .args(["--port", &port.to_string(), "--save", "", "--appendonly", "no"])
+        .args(args)
+        .current_dir(std::env::temp_dir())
+        .stdout(Stdio::null())
+        .spawn()
+        .ok()?;
+    std::thread::sleep(Duration::from_millis(200));
+    Some(RedisServer(child))
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{free_port, start};
+use rate_limiter::{concurrency::ConcurrencyConfig, store::StoreConfig, RateLimiter};
+use wiremock::{matchers::any, Mock, MockServer, ResponseTemplate};
+
+/// An upstream taking `delay` to answer every request.
+async fn slow_upstream(delay: Duration) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200).set_delay(delay))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn config(per_client: Option<u64>, global: Option<u64>, max_queued: usize) -> ConcurrencyConfig {
+    ConcurrencyConfig {
+        per_client,
+        global,
+        max_queued,
+        queue_timeout: Duration::from_secs(2),
+        lease_ttl: Duration::from_secs(30),
+    }
+}
+
+/// Sends two requests at once and returns their statuses, sorted.
+async fn two_concurrent(config: ConcurrencyConfig, delay: Duration) -> Vec<u16> {
+    let upstream = slow_upstream(delay).await;
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 100).with_concurrency_limits(config);
+    let uri = start(limiter).await;
+
+    let (first, second) = tokio::join!(reqwest::get(format!("{}/a", uri)), reqwest::get(format!("{}/b", uri)));
+    let mut statuses = vec![first.unwrap().status().as_u16(), second.unwrap().status().as_u16()];
+    statuses.sort();
+    statuses
+}
+
+#[actix_web::test]
+async fn client_over_its_cap_gets_429() {
+    let statuses = two_concurrent(config(Some(1), None, 0), Duration::from_millis(300)).await;
+    assert_eq!(statuses, vec![200, 429]);
+}
+
+#[actix_web::test]
+async fn global_cap_gets_503() {
+    let statuses = two_concurrent(config(None, Some(1), 0), Duration::from_millis(300)).await;
+    assert_eq!(statuses, vec![200, 503]);
+}
+
+#[actix_web::test]
+async fn queued_request_waits_for_a_free_slot() {
+    let statuses = two_concurrent(config(Some(1), None, 1), Duration::from_millis(300)).await;
+    assert_eq!(statuses, vec![200, 200]);
+}
+
+#[actix_web::test]
+async fn queued_request_times_out() {
+    let mut config = config(Some(1), None, 1);
+    config.queue_timeout = Duration::from_millis(100);
+    let statuses = two_concurrent(config, Duration::from_millis(500)).await;
+    assert_eq!(statuses, vec![200, 429]);
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{free_port, get_status, start, upstream, wait_for_fresh_window};
+use rate_limiter::{edge::EdgeLimits, store::StoreConfig, RateLimiter};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use wiremock::MockServer;
+
+async fn start_with(edge: EdgeLimits) -> (String, MockServer) {
+    let upstream = upstream().await;
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 100).with_edge_limits(edge);
+    (start(limiter).await, upstream)
+}
+
+/// Sends raw bytes on a new connection and returns the status line of the response.
+async fn raw_status(stream: &mut TcpStream, request: &[u8]) -> String {
+    stream.write_all(request).await.unwrap();
+    let mut response = vec![0; 1024];
+    let read = stream.read(&mut response).await.unwrap();
+    String::from_utf8_lossy(&response[..read]).lines().next().unwrap_or_default().to_string()
+}
+
+#[actix_web::test]
+async fn rejects_large_bodies_with_413() {
+    let (uri, _upstream) = start_with(EdgeLimits { max_body_bytes: 16, ..Default::default() }).await;
+    let client = reqwest::Client::new();
+
+    let response = client.post(format!("{}/todo", uri)).body("x".repeat(17)).send().await.unwrap();
+    assert_eq!(response.status(), 413);
+    let response = client.post(format!("{}/todo", uri)).body("x".repeat(16)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[actix_web::test]
+async fn rejects_large_headers_with_431() {
+    let (uri, _upstream) = start_with(EdgeLimits { max_header_bytes: 256, ..Default::default() }).await;
+
+    let response = reqwest::Client::new().get(format!("{}/todos", uri)).header("x-big", "x".repeat(300)).send().await.unwrap();
+    assert_eq!(response.status(), 431);
+}
+
+#[actix_web::test]
+async fn times_out_slow_bodies_with_408() {
+    let (uri, _upstream) = start_with(EdgeLimits { body_read_timeout: Duration::from_millis(200), ..Default::default() }).await;
+    let mut stream = TcpStream::connect(uri.trim_start_matches("http://")).await.unwrap();
+
+    // Announce a body that never arrives
+    let status = raw_status(&mut stream, b"POST /todo HTTP/1.1\r\nhost: localhost\r\ncontent-length: 10\r\n\r\n").await;
+    assert!(status.starts_with("HTTP/1.1 408"), "{}", status);
+}
+
+#[actix_web::test]
+async fn limits_connections_per_ip() {
+    let (uri, _upstream) = start_with(EdgeLimits { max_connections_per_ip: Some(1), ..Default::default() }).await;
+    let addr = uri.trim_start_matches("http://");
+    let request = b"GET /todos HTTP/1.1\r\nhost: localhost\r\n\r\n";
+
+    // Let the connection of the readiness probe be closed first
+    actix_web::rt::time::sleep(Duration::from_millis(100)).await;
+    let mut first = TcpStream::connect(addr).await.unwrap();
+    assert!(raw_status(&mut first, request).await.starts_with("HTTP/1.1 200"));
+    let mut second = TcpStream::connect(addr).await.unwrap();
+    assert!(raw_status(&mut second, request).await.starts_with("HTTP/1.1 429"));
+
+    // The slot is freed once the first connection is closed
+    drop(first);
+    drop(second);
+    actix_web::rt::time::sleep(Duration::from_millis(100)).await;
+    let mut third = TcpStream::connect(addr).await.unwrap();
+    assert!(raw_status(&mut third, request).await.starts_with("HTTP/1.1 200"));
+}
+
+#[actix_web::test]
+async fn rejected_requests_are_not_counted() {
+    let upstream = upstream().await;
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 1)
+        .with_edge_limits(EdgeLimits { max_body_bytes: 16, ..Default::default() });
+    let uri = start(limiter).await;
+    wait_for_fresh_window(10).await;
+
+    let response = reqwest::Client::new().post(format!("{}/todo", uri)).body("x".repeat(17)).send().await.unwrap();
+    assert_eq!(response.status(), 413);
+    assert_eq!(get_status(&uri).await, 200);
+    assert_eq!(get_status(&uri).await, 429);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{free_port, get_status, spawn_redis, start, upstream, wait_for_fresh_window};
+use rate_limiter::{store::StoreConfig, FailurePolicy, RateLimiter};
+
+/// Nothing listens on port 1, so every Redis operation fails immediately.
+fn unreachable_redis() -> StoreConfig {
+    StoreConfig::Redis("redis://127.0.0.1:1".to_string())
+}
+
+#[actix_web::test]
+async fn fail_open_forwards_while_redis_is_down() {
+    let upstream = upstream().await;
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), unreachable_redis(), 1)
+        .with_failure_policy(FailurePolicy::Open);
+    let uri = start(limiter).await;
+
+    for _ in 0..3 {
+        assert_eq!(get_status(&uri).await, 200);
+    }
+}
+
+#[actix_web::test]
+async fn fail_closed_rejects_with_503_while_redis_is_down() {
+    let upstream = upstream().await;
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), unreachable_redis(), 1)
+        .with_failure_policy(FailurePolicy::Closed);
+    let uri = start(limiter).await;
+
+    assert_eq!(get_status(&uri).await, 503);
+}
+
+#[actix_web::test]
+async fn local_fallback_keeps_limiting_while_redis_is_down() {
+    let upstream = upstream().await;
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), unreachable_redis(), 2)
+        .with_failure_policy(FailurePolicy::Local);
+    let uri = start(limiter).await;
+    wait_for_fresh_window(10).await;
+
+    assert_eq!(get_status(&uri).await, 200);
+    assert_eq!(get_status(&uri).await, 200);
+    assert_eq!(get_status(&uri).await, 429);
+}
+
+#[actix_web::test]
+#[ignore = "needs redis-server, run with --ignored"]
+async fn fail_closed_after_redis_is_killed_mid_run() {
+    let redis_port = free_port();
+    let redis = spawn_redis(redis_port, &[]).expect("redis-server not found");
+
+    let upstream = upstream().await;
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Redis(format!("redis://127.0.0.1:{}", redis_port)), 100)
+        .with_failure_policy(FailurePolicy::Closed);
+    let uri = start(limiter).await;
+    assert_eq!(get_status(&uri).await, 200);
+
+    drop(redis);
+    assert_eq!(get_status(&uri).await, 503);
+}
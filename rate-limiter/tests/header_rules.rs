@@ -0,0 +1,100 @@
+mod common;
+
+use common::{free_port, start};
+use rate_limiter::{headers::HeaderRules, store::StoreConfig, RateLimiter};
+use wiremock::{
+    matchers::{header, header_exists},
+    Mock, MockServer, ResponseTemplate,
+};
+
+fn rules(content: &str) -> HeaderRules {
+    let path = std::env::temp_dir().join(format!("rate-limiter-test-headers-{}.json", free_port()));
+    std::fs::write(&path, content).unwrap();
+    HeaderRules::from_file(&path.to_string_lossy()).unwrap()
+}
+
+async fn start_with(upstream: &MockServer, rules: HeaderRules) -> String {
+    start(RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 100).with_header_rules(rules)).await
+}
+
+#[actix_web::test]
+async fn rewrites_request_headers() {
+    let upstream = MockServer::start().await;
+    Mock::given(header("x-forwarded-for", "127.0.0.1"))
+        .and(header("x-app-version", "2"))
+        .and(header("x-route", "GET /todos"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&upstream)
+        .await;
+    Mock::given(header_exists("x-client-version")).respond_with(ResponseTemplate::new(400)).mount(&upstream).await;
+    let rules = rules(
+        r#"{ "routes": [{ "request": [
+            { "action": "set", "name": "x-forwarded-for", "value": "{client_ip}" },
+            { "action": "rename", "from": "x-client-version", "to": "x-app-version" },
+            { "action": "add", "name": "x-route", "value": "{method} {path}" }
+        ] }] }"#,
+    );
+    let uri = start_with(&upstream, rules).await;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/todos", uri))
+        .header("x-forwarded-for", "10.0.0.1")
+        .header("x-client-version", "2")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[actix_web::test]
+async fn strips_internal_response_headers_per_route() {
+    let upstream = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ResponseTemplate::new(200).insert_header("server-status", "ok"))
+        .mount(&upstream)
+        .await;
+    let rules = rules(
+        r#"{ "routes": [{ "path_prefix": "/todos", "response": [
+            { "action": "remove", "name": "server-status" },
+            { "action": "set", "name": "x-served-by", "value": "{backend}" }
+        ] }] }"#,
+    );
+    let uri = start_with(&upstream, rules).await;
+
+    let response = reqwest::get(format!("{}/todos", uri)).await.unwrap();
+    assert!(!response.headers().contains_key("server-status"));
+    assert_eq!(response.headers()["x-served-by"], upstream.uri().as_str());
+    assert_eq!(response.headers()["rate-limiter-status"], "ok");
+
+    // Other routes are left alone
+    let response = reqwest::get(format!("{}/health", uri)).await.unwrap();
+    assert_eq!(response.headers()["server-status"], "ok");
+}
+
+#[actix_web::test]
+async fn example_rules_strip_server_headers() {
+    let upstream = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("server", "todo-service")
+                .insert_header("x-powered-by", "actix-web")
+                .insert_header("server-status", "ok"),
+        )
+        .mount(&upstream)
+        .await;
+    let rules = HeaderRules::from_file("headers.example.json").unwrap();
+    let uri = start_with(&upstream, rules).await;
+
+    let response = reqwest::get(format!("{}/todos", uri)).await.unwrap();
+    for name in ["server", "x-powered-by", "server-status"] {
+        assert!(!response.headers().contains_key(name), "{} was not stripped", name);
+    }
+}
+
+#[test]
+fn rejects_invalid_header_names() {
+    let path = std::env::temp_dir().join(format!("rate-limiter-test-headers-{}.json", free_port()));
+    std::fs::write(&path, r#"{ "routes": [{ "request": [{ "action": "remove", "name": "bad name" }] }] }"#).unwrap();
+    assert!(HeaderRules::from_file(&path.to_string_lossy()).is_err());
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use std::{collections::HashMap, time::Duration};
+
+use common::{free_port, get_status, start, upstream, wait_for_fresh_window};
+use rate_limiter::{
+    store::StoreConfig,
+    tiers::{KeySource, Tier, TierConfig},
+    RateLimiter, API_KEY_HEADER,
+};
+
+#[actix_web::test]
+async fn limits_clients_without_redis() {
+    let upstream = upstream().await;
+    let uri = start(RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 3)).await;
+    wait_for_fresh_window(10).await;
+
+    for _ in 0..3 {
+        assert_eq!(get_status(&uri).await, 200);
+    }
+    assert_eq!(get_status(&uri).await, 429);
+}
+
+#[actix_web::test]
+async fn expired_windows_are_reset() {
+    let upstream = upstream().await;
+    let tiers = TierConfig {
+        tiers: HashMap::from([("free".to_string(), Tier { per_second: Some(1), ..Default::default() })]),
+        keys: KeySource::File(HashMap::from([("key".to_string(), "free".to_string())])),
+    };
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 100).with_tiers(tiers);
+    let uri = start(limiter).await;
+
+    let client = reqwest::Client::new();
+    let get = || client.get(format!("{}/todos", uri)).header(API_KEY_HEADER, "key").send();
+
+    wait_for_fresh_window(1).await;
+
+    assert_eq!(get().await.unwrap().status(), 200);
+    assert_eq!(get().await.unwrap().status(), 429);
+
+    actix_web::rt::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(get().await.unwrap().status(), 200);
+}
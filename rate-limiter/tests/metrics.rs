@@ -0,0 +1,45 @@
+mod common;
+
+use common::{free_port, get_status, start, upstream, wait_for_fresh_window};
+use rate_limiter::{store::StoreConfig, RateLimiter};
+
+async fn metrics(uri: &str) -> String {
+    reqwest::get(format!("{}/rate-limiter/metrics", uri)).await.unwrap().text().await.unwrap()
+}
+
+#[actix_web::test]
+async fn counts_allowed_and_rejected_requests() {
+    let upstream = upstream().await;
+    let uri = start(RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 1)).await;
+    wait_for_fresh_window(10).await;
+
+    assert_eq!(get_status(&uri).await, 200);
+    assert_eq!(get_status(&uri).await, 429);
+
+    let metrics = metrics(&uri).await;
+    assert!(metrics.contains(r#"rate_limiter_requests_total{decision="allowed",key_type="ip",rule="quota"} 1"#));
+    assert!(metrics.contains(r#"rate_limiter_requests_total{decision="rejected",key_type="ip",rule="default"} 1"#));
+}
+
+#[actix_web::test]
+async fn records_store_latency_and_errors() {
+    let upstream = upstream().await;
+    let store = StoreConfig::Redis("redis://127.0.0.1:1".to_string());
+    let uri = start(RateLimiter::new(free_port(), upstream.uri(), store, 10)).await;
+
+    // The default failure policy forwards the request while the store is unreachable
+    assert_eq!(get_status(&uri).await, 200);
+
+    let metrics = metrics(&uri).await;
+    assert!(metrics.contains(r#"rate_limiter_store_duration_seconds_count{operation="incr"} 1"#));
+    assert!(metrics.contains(r#"rate_limiter_store_errors_total{operation="incr"} 1"#));
+}
+
+#[actix_web::test]
+async fn forwards_the_upstream_metrics() {
+    let upstream = upstream().await;
+    let uri = start(RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 10)).await;
+
+    let body = reqwest::get(format!("{}/metrics", uri)).await.unwrap().text().await.unwrap();
+    assert_eq!(body, "upstream");
+}
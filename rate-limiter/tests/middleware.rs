@@ -0,0 +1,35 @@
+use actix_web::{test, web, App, HttpResponse};
+use rate_limiter::{store::StoreConfig, RateLimitConfig};
+
+#[actix_web::test]
+async fn limits_an_embedding_app() {
+    let rate_limit = RateLimitConfig::new(StoreConfig::Memory, 2).start().await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .wrap(rate_limit)
+            .route("/todos", web::get().to(|| async { HttpResponse::Ok().body("todos") })),
+    )
+    .await;
+    let request = || test::TestRequest::get().uri("/todos").peer_addr("10.0.0.1:4000".parse().unwrap()).to_request();
+
+    let response = test::call_service(&app, request()).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(test::read_body(response).await, "todos");
+    test::call_service(&app, request()).await;
+
+    let response = test::call_service(&app, request()).await;
+    assert_eq!(response.status(), 429);
+    assert!(response.headers().contains_key("retry-after"));
+}
+
+#[actix_web::test]
+async fn shares_counters_between_clones() {
+    let rate_limit = RateLimitConfig::new(StoreConfig::Memory, 1).start().await.unwrap();
+    let route = || web::get().to(|| async { HttpResponse::Ok().finish() });
+    let first = test::init_service(App::new().wrap(rate_limit.clone()).route("/", route())).await;
+    let second = test::init_service(App::new().wrap(rate_limit).route("/", route())).await;
+    let request = || test::TestRequest::get().peer_addr("10.0.0.2:4000".parse().unwrap()).to_request();
+
+    assert_eq!(test::call_service(&first, request()).await.status(), 200);
+    assert_eq!(test::call_service(&second, request()).await.status(), 429);
+}
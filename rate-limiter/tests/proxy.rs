@@ -0,0 +1,100 @@
+mod common;
+
+use common::{free_port, start, upstream, wait_for_fresh_window};
+use rate_limiter::{store::StoreConfig, RateLimiter};
+use serde_json::Value;
+use wiremock::{
+    matchers::{body_string, header, method, path, query_param},
+    Mock, MockServer, ResponseTemplate,
+};
+
+async fn start_proxy(upstream: &MockServer, request_limit: usize) -> String {
+    start(RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, request_limit)).await
+}
+
+#[actix_web::test]
+async fn rejects_with_a_json_body_and_retry_after() {
+    let upstream = upstream().await;
+    let uri = start_proxy(&upstream, 1).await;
+    wait_for_fresh_window(10).await;
+
+    assert_eq!(reqwest::get(format!("{}/todos", uri)).await.unwrap().status(), 200);
+    let response = reqwest::get(format!("{}/todos", uri)).await.unwrap();
+    assert_eq!(response.status(), 429);
+    let retry_after: u64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!((1..=10).contains(&retry_after));
+
+    let body: Value = serde_json::from_slice(&response.bytes().await.unwrap()).unwrap();
+    assert_eq!(body["quota"], "default");
+    assert_eq!(body["limit"], 1);
+    assert_eq!(body["retry_after"], retry_after);
+    // Rejected requests never reach the upstream
+    assert_eq!(upstream.received_requests().await.unwrap().len(), 1);
+}
+
+#[actix_web::test]
+async fn forwards_request_and_response_headers() {
+    let upstream = MockServer::start().await;
+    Mock::given(header("x-trace", "abc"))
+        .respond_with(ResponseTemplate::new(200).insert_header("x-upstream", "yes"))
+        .mount(&upstream)
+        .await;
+    let uri = start_proxy(&upstream, 10).await;
+
+    let response = reqwest::Client::new().get(format!("{}/todos", uri)).header("x-trace", "abc").send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["x-upstream"], "yes");
+    assert_eq!(response.headers()["rate-limiter-status"], "ok");
+}
+
+#[actix_web::test]
+async fn passes_method_path_query_and_body_through() {
+    let upstream = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .and(path("/todo/7"))
+        .and(query_param("notify", "true"))
+        .and(body_string(r#"{"title":"Buy milk"}"#))
+        .respond_with(ResponseTemplate::new(201).set_body_string("updated"))
+        .mount(&upstream)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/todo/7"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&upstream)
+        .await;
+    let uri = start_proxy(&upstream, 10).await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .put(format!("{}/todo/7?notify=true", uri))
+        .body(r#"{"title":"Buy milk"}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    assert_eq!(response.text().await.unwrap(), "updated");
+
+    let response = client.delete(format!("{}/todo/7", uri)).send().await.unwrap();
+    assert_eq!(response.status(), 204);
+}
+
+#[actix_web::test]
+async fn passes_upstream_errors_through() {
+    let upstream = MockServer::start().await;
+    Mock::given(path("/missing")).respond_with(ResponseTemplate::new(404)).mount(&upstream).await;
+    Mock::given(path("/broken")).respond_with(ResponseTemplate::new(500)).mount(&upstream).await;
+    let uri = start_proxy(&upstream, 10).await;
+
+    assert_eq!(reqwest::get(format!("{}/missing", uri)).await.unwrap().status(), 404);
+    assert_eq!(reqwest::get(format!("{}/broken", uri)).await.unwrap().status(), 500);
+}
+
+#[actix_web::test]
+async fn maps_an_unreachable_upstream_to_500() {
+    let limiter = RateLimiter::new(free_port(), format!("http://127.0.0.1:{}", free_port()), StoreConfig::Memory, 10);
+    let uri = start(limiter).await;
+
+    let response = reqwest::get(format!("{}/todos", uri)).await.unwrap();
+    assert_eq!(response.status(), 500);
+    assert!(response.text().await.unwrap().starts_with("Forwarding error"));
+}
@@ -0,0 +1,113 @@
+//! Runs the rate limiter against locally spawned Redis Cluster and Sentinel setups.
+//! Needs `redis-server` and `redis-cli`, so the tests are ignored by default; run them with `--ignored`.
+
+mod common;
+
+use std::{
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use common::{free_port, get_status, spawn_redis, start, upstream, wait_for_fresh_window, RedisServer};
+use rate_limiter::{store::StoreConfig, FailurePolicy, RateLimiter};
+
+fn redis_cli(args: &[&str]) -> Option<String> {
+    let output = Command::new("redis-cli").args(args).stderr(Stdio::null()).output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Waits until `condition` holds, polling every 100ms for up to `timeout`.
+fn wait_until(timeout: Duration, mut condition: impl FnMut() -> bool) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if condition() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    false
+}
+
+#[actix_web::test]
+#[ignore = "needs redis-server and redis-cli, run with --ignored"]
+async fn limits_through_redis_cluster() {
+    let ports: Vec<u16> = (0..3).map(|_| free_port()).collect();
+    let mut nodes: Vec<RedisServer> = Vec::new();
+    for port in &ports {
+        let config_file = format!("rate-limiter-test-nodes-{}.conf", port);
+        let node = spawn_redis(*port, &["--cluster-enabled", "yes", "--cluster-config-file", &config_file])
+            .expect("redis-server not found");
+        nodes.push(node);
+    }
+
+    let addresses: Vec<String> = ports.iter().map(|port| format!("127.0.0.1:{}", port)).collect();
+    let mut create = vec!["--cluster", "create"];
+    create.extend(addresses.iter().map(String::as_str));
+    create.extend(["--cluster-replicas", "0", "--cluster-yes"]);
+    redis_cli(&create).expect("redis-cli not found");
+    let port = ports[0].to_string();
+    assert!(wait_until(Duration::from_secs(10), || {
+        redis_cli(&["-p", &port, "cluster", "info"]).is_some_and(|info| info.contains("cluster_state:ok"))
+    }));
+
+    let upstream = upstream().await;
+    let seeds = addresses.iter().map(|address| format!("redis://{}", address)).collect();
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), StoreConfig::RedisCluster(seeds), 2)
+        .with_failure_policy(FailurePolicy::Closed);
+    let uri = start(limiter).await;
+    wait_for_fresh_window(10).await;
+
+    assert_eq!(get_status(&uri).await, 200);
+    assert_eq!(get_status(&uri).await, 200);
+    assert_eq!(get_status(&uri).await, 429);
+}
+
+#[actix_web::test]
+#[ignore = "needs redis-server, run with --ignored"]
+async fn follows_sentinel_failover() {
+    let (primary_port, replica_port, sentinel_port) = (free_port(), free_port(), free_port());
+    let primary = spawn_redis(primary_port, &[]).expect("redis-server not found");
+    let replica_of = primary_port.to_string();
+    let _replica = spawn_redis(replica_port, &["--replicaof", "127.0.0.1", &replica_of]).unwrap();
+
+    // Sentinel rewrites its config file, so each run gets its own
+    let config = std::env::temp_dir().join(format!("rate-limiter-test-sentinel-{}.conf", sentinel_port));
+    std::fs::write(
+        &config,
+        format!(
+            "port {}\n\
+             sentinel monitor primary 127.0.0.1 {} 1\n\
+             sentinel down-after-milliseconds primary 500\n\
+             sentinel failover-timeout primary 2000\n",
+            sentinel_port, primary_port
+        ),
+    )
+    .unwrap();
+    let sentinel = Command::new("redis-server")
+        .arg(&config)
+        .arg("--sentinel")
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let _sentinel = RedisServer::from(sentinel);
+    std::thread::sleep(Duration::from_millis(500));
+
+    let upstream = upstream().await;
+    let store = StoreConfig::RedisSentinel {
+        sentinels: vec![format!("redis://127.0.0.1:{}", sentinel_port)],
+        master_name: "primary".to_string(),
+    };
+    let limiter = RateLimiter::new(free_port(), upstream.uri(), store, 1_000).with_failure_policy(FailurePolicy::Closed);
+    let uri = start(limiter).await;
+    assert_eq!(get_status(&uri).await, 200);
+
+    // Once sentinel promotes the replica, requests are counted again
+    drop(primary);
+    let start = Instant::now();
+    let mut status = get_status(&uri).await;
+    while status != 200 && start.elapsed() < Duration::from_secs(20) {
+        actix_web::rt::time::sleep(Duration::from_millis(200)).await;
+        status = get_status(&uri).await;
+    }
+    assert_eq!(status, 200);
+}
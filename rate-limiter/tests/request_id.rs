@@ -0,0 +1,60 @@
+mod common;
+
+use common::{free_port, start, upstream, wait_for_fresh_window};
+use rate_limiter::{request_id::REQUEST_ID_HEADER, store::StoreConfig, RateLimiter};
+use wiremock::{
+    matchers::{header, header_exists},
+    Mock, MockServer, ResponseTemplate,
+};
+
+#[actix_web::test]
+async fn forwards_and_echoes_the_callers_id() {
+    let upstream = MockServer::start().await;
+    Mock::given(header(REQUEST_ID_HEADER, "abc-123")).respond_with(ResponseTemplate::new(200)).mount(&upstream).await;
+    let uri = start(RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 10)).await;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/todos", uri))
+        .header(REQUEST_ID_HEADER, "abc-123")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()[REQUEST_ID_HEADER], "abc-123");
+}
+
+#[actix_web::test]
+async fn generates_an_id_when_missing_or_invalid() {
+    let upstream = MockServer::start().await;
+    Mock::given(header_exists(REQUEST_ID_HEADER)).respond_with(ResponseTemplate::new(200)).mount(&upstream).await;
+    let uri = start(RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 10)).await;
+
+    let response = reqwest::get(format!("{}/todos", uri)).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let generated = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+    assert_eq!(generated.len(), 36);
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/todos", uri))
+        .header(REQUEST_ID_HEADER, "x".repeat(200))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[REQUEST_ID_HEADER].len(), 36);
+}
+
+#[actix_web::test]
+async fn rejections_carry_the_id() {
+    let upstream = upstream().await;
+    let uri = start(RateLimiter::new(free_port(), upstream.uri(), StoreConfig::Memory, 0)).await;
+    wait_for_fresh_window(10).await;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/todos", uri))
+        .header(REQUEST_ID_HEADER, "rejected-1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 429);
+    assert_eq!(response.headers()[REQUEST_ID_HEADER], "rejected-1");
+}
@@ -1,56 +0,0 @@
-use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
-use actix_web::http::header;
-use futures::future::{ok, Ready};
-use std::task::{Context, Poll};
-use std::pin::Pin;
-
-// Middleware to add a custom header
-pub struct CustomHeader;
-
-impl<S, B> Transform<S, ServiceRequest> for CustomHeader
-where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
-    S::Future: 'static,
-    B: 'static,
-{
-    type Response = ServiceResponse<B>;
-    type Error = actix_web::Error;
-    type Transform = CustomHeaderMiddleware<S>;
-    type InitError = ();
-    type Future = Ready<Result<Self::Transform, Self::InitError>>;
-
-    fn new_transform(&self, service: S) -> Self::Future {
-        ok(CustomHeaderMiddleware { service })
-    }
-}
-
-pub struct CustomHeaderMiddleware<S> {
-    service: S,
-}
-
-impl<S, B> Service<ServiceRequest> for CustomHeaderMiddleware<S>
-where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
-    S::Future: 'static,
-    B: 'static,
-{
-    type Response = ServiceResponse<B>;
-    type Error = actix_web::Error;
-    type Future = Pin<Box<dyn futures::Future<Output = Result<Self::Response, Self::Error>>>>;
-
-    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.service.poll_ready(cx)
-    }
-
-    fn call(&self, req: ServiceRequest) -> Self::Future {
-        let fut = self.service.call(req);
-        Box::pin(async move {
-            let mut res = fut.await?;
-            res.headers_mut().insert(
-                header::HeaderName::from_static("server-status"),
-                header::HeaderValue::from_static("ok"),
-            );
-            Ok(res)
-        })
-    }
-}
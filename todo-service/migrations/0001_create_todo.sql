@@ -1,9 +1,10 @@
-CREATE TABLE todo (
+-- The original schema. IF NOT EXISTS so databases set up by hand from todo.sql are adopted.
+CREATE TABLE IF NOT EXISTS todo (
     id SERIAL PRIMARY KEY,
     title VARCHAR(255) NOT NULL,
     description TEXT,
-    status VARCHAR(50) DEFAULT 'pending',  -- Status of the task (e.g., pending, completed)
+    status VARCHAR(50) DEFAULT 'pending',
     due_date DATE,
     created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-);
\ No newline at end of file
+);
@@ -0,0 +1,27 @@
+-- Statuses and their allowed transitions are defined in src/status.rs.
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'todo_status') THEN
+        CREATE TYPE todo_status AS ENUM ('pending', 'in_progress', 'completed', 'archived');
+    END IF;
+
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'todo' AND column_name = 'status' AND data_type = 'character varying'
+    ) THEN
+        ALTER TABLE todo
+            ALTER COLUMN status DROP DEFAULT,
+            -- Statuses written before the enum existed are mapped to the closest one
+            ALTER COLUMN status TYPE todo_status USING (
+                CASE
+                    WHEN status IN ('pending', 'in_progress', 'completed', 'archived') THEN status
+                    WHEN status IN ('done', 'complete') THEN 'completed'
+                    WHEN status IN ('in progress', 'in-progress', 'doing') THEN 'in_progress'
+                    ELSE 'pending'
+                END
+            )::todo_status,
+            ALTER COLUMN status SET DEFAULT 'pending',
+            ALTER COLUMN status SET NOT NULL;
+    END IF;
+END
+$$;
@@ -0,0 +1,13 @@
+-- Keeps updated_at current on every update, whoever writes the row.
+CREATE OR REPLACE FUNCTION set_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = CURRENT_TIMESTAMP;
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS todo_set_updated_at ON todo;
+CREATE TRIGGER todo_set_updated_at
+    BEFORE UPDATE ON todo
+    FOR EACH ROW
+    EXECUTE FUNCTION set_updated_at();
@@ -0,0 +1,18 @@
+CREATE TABLE users (
+    id SERIAL PRIMARY KEY,
+    email VARCHAR(255) NOT NULL UNIQUE,  -- Stored lowercased
+    password_hash TEXT NOT NULL,         -- Argon2 PHC string
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Refresh tokens are single use: each is deleted when exchanged for a new pair, or on logout
+CREATE TABLE refresh_tokens (
+    jti TEXT PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    expires_at TIMESTAMP NOT NULL
+);
+CREATE INDEX refresh_tokens_user_id ON refresh_tokens (user_id);
+
+-- Todos created before users existed have no owner and are no longer visible through the API
+ALTER TABLE todo ADD COLUMN user_id INTEGER REFERENCES users (id) ON DELETE CASCADE;
+CREATE INDEX todo_user_id ON todo (user_id);
@@ -0,0 +1,27 @@
+CREATE TYPE list_role AS ENUM ('owner', 'editor', 'viewer');
+
+CREATE TABLE lists (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER lists_set_updated_at
+    BEFORE UPDATE ON lists
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- Every list keeps at least one owner, enforced by the API
+CREATE TABLE list_members (
+    list_id INTEGER NOT NULL REFERENCES lists (id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role list_role NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (list_id, user_id)
+);
+CREATE INDEX list_members_user_id ON list_members (user_id);
+
+-- Todos in a list belong to it; `user_id` is then the member who created them.
+-- Todos without a list are the personal todos of `user_id`.
+ALTER TABLE todo ADD COLUMN list_id INTEGER REFERENCES lists (id) ON DELETE CASCADE;
+CREATE INDEX todo_list_id ON todo (list_id);
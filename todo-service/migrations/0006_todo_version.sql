@@ -0,0 +1,14 @@
+-- Bumped on every update, whoever writes the row; sent as the ETag of a todo.
+ALTER TABLE todo ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+CREATE OR REPLACE FUNCTION bump_version() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.version = OLD.version + 1;
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER todo_bump_version
+    BEFORE UPDATE ON todo
+    FOR EACH ROW
+    EXECUTE FUNCTION bump_version();
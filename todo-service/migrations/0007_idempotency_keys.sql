@@ -0,0 +1,9 @@
+-- Responses of `POST /todo` by `Idempotency-Key`, so a retried request returns the todo it created
+CREATE TABLE idempotency_keys (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    key VARCHAR(255) NOT NULL,
+    request JSONB NOT NULL,         -- Body of the first request, a reused key must come with the same one
+    response JSONB,                 -- Set in the transaction that claimed the key
+    expires_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (user_id, key)
+);
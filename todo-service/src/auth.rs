@@ -0,0 +1,138 @@
+use std::{
+    future::{ready, Ready},
+    sync::LazyLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use actix_web::{dev::Payload, web::Data, FromRequest, HttpRequest};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{error::ApiError, AppState};
+
+/// Issues and checks the JWTs of the API: short-lived access tokens sent as
+/// `Authorization: Bearer <token>`, and refresh tokens exchanged at `/auth/refresh`.
+pub struct Auth {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    access_ttl: Duration,
+    refresh_ttl: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    Access,
+    Refresh,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// The user id.
+    pub sub: i32,
+    pub kind: TokenKind,
+    /// Refresh tokens are single use, tracked by this id in `refresh_tokens`.
+    pub jti: String,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+/// A freshly issued token with its lifetime.
+pub struct IssuedToken {
+    pub token: String,
+    pub jti: String,
+    pub ttl: Duration,
+}
+
+impl Auth {
+    pub fn new(secret: &str) -> Self {
+        Auth {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            access_ttl: Duration::from_secs(15 * 60),
+            refresh_ttl: Duration::from_secs(30 * 24 * 60 * 60),
+        }
+    }
+
+    pub fn with_access_ttl(mut self, ttl: Duration) -> Self {
+        self.access_ttl = ttl;
+        self
+    }
+
+    pub fn with_refresh_ttl(mut self, ttl: Duration) -> Self {
+        self.refresh_ttl = ttl;
+        self
+    }
+
+    pub fn issue(&self, user_id: i32, kind: TokenKind) -> IssuedToken {
+        let ttl = match kind {
+            TokenKind::Access => self.access_ttl,
+            TokenKind::Refresh => self.refresh_ttl,
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let claims = Claims {
+            sub: user_id,
+            kind,
+            jti: Uuid::new_v4().to_string(),
+            iat: now,
+            exp: now + ttl.as_secs(),
+        };
+        let token = encode(&Header::default(), &claims, &self.encoding_key).expect("Failed to encode token");
+        IssuedToken { token, jti: claims.jti, ttl }
+    }
+
+    /// Checks the signature, expiry and kind of a token.
+    pub fn verify(&self, token: &str, kind: TokenKind) -> Result<Claims, ApiError> {
+        let claims = decode::<Claims>(token, &self.decoding_key, &Validation::default())
+            .map_err(|_| ApiError::Unauthorized("Invalid or expired token"))?
+            .claims;
+        if claims.kind != kind {
+            return Err(ApiError::Unauthorized("Invalid or expired token"));
+        }
+        Ok(claims)
+    }
+}
+
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("Failed to hash password")
+        .to_string()
+}
+
+/// Checked against when logging in with an unknown email, so that takes as long as a wrong password.
+pub static DUMMY_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| hash_password("not the password of any user"));
+
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+/// The user making the request, authenticated by the access token in the `Authorization` header.
+/// Handlers taking it answer `401` to anonymous requests.
+pub struct AuthUser {
+    pub id: i32,
+}
+
+impl FromRequest for AuthUser {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let token = req
+            .headers()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let Some(token) = token else {
+            return ready(Err(ApiError::Unauthorized("Missing bearer token")));
+        };
+        let state = req.app_data::<Data<AppState>>().expect("AppState is not configured");
+        ready(state.auth.verify(token, TokenKind::Access).map(|claims| AuthUser { id: claims.sub }))
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{auth::AuthUser, error::ApiError, request_id::RequestId, AppState};
+
+/// Role of a member of a shared list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "list_role", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ListRole {
+    Owner,
+    Editor,
+    Viewer,
+}
+
+/// What a request does to a shared list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Read the list, its members and its todos.
+    View,
+    /// Create, update and delete the todos of the list.
+    EditTodos,
+    Rename,
+    /// Add members, change their role and remove them.
+    ManageMembers,
+    Delete,
+}
+
+impl ListRole {
+    pub const ALL: [ListRole; 3] = [ListRole::Owner, ListRole::Editor, ListRole::Viewer];
+
+    /// Viewers can only read, editors can also change todos, owners can do anything.
+    pub fn can(self, action: Action) -> bool {
+        match action {
+            Action::View => true,
+            Action::EditTodos => matches!(self, ListRole::Owner | ListRole::Editor),
+            Action::Rename | Action::ManageMembers | Action::Delete => self == ListRole::Owner,
+        }
+    }
+}
+
+impl Action {
+    pub const ALL: [Action; 5] = [Action::View, Action::EditTodos, Action::Rename, Action::ManageMembers, Action::Delete];
+
+    fn denied(self) -> &'static str {
+        match self {
+            Action::View => "You can't view this list",
+            Action::EditTodos => "Only owners and editors can change the todos of this list",
+            Action::Rename => "Only owners can rename this list",
+            Action::ManageMembers => "Only owners can manage the members of this list",
+            Action::Delete => "Only owners can delete this list",
+        }
+    }
+}
+
+/// Checks that `user` may do `action` on a list and returns their role.
+///
+/// Lists the user isn't a member of are reported as missing, so their ids don't leak.
+pub(crate) async fn authorize(
+    state: &AppState,
+    user: &AuthUser,
+    list_id: i32,
+    action: Action,
+    request_id: &RequestId,
+) -> Result<ListRole, ApiError> {
+    match sqlx::query_scalar::<_, ListRole>("SELECT role FROM list_members WHERE list_id = $1 AND user_id = $2")
+        .bind(list_id)
+        .bind(user.id)
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(Some(role)) if role.can(action) => Ok(role),
+        Ok(Some(_)) => Err(ApiError::Forbidden(action.denied())),
+        Ok(None) => Err(ApiError::NotFound("List not found")),
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to check permissions"))
+        },
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt::Display;
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::StatusCode,
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Serialize;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+/// Errors returned by the todo API, sent as RFC 7807 `application/problem+json`.
+#[derive(Debug)]
+pub enum ApiError {
+    /// Malformed request, e.g. invalid JSON or query parameters.
+    BadRequest(String),
+    /// Well-formed request with invalid fields.
+    Validation(Vec<FieldError>),
+    UnsupportedMediaType(String),
+    PayloadTooLarge(String),
+    /// Missing, invalid or expired credentials.
+    Unauthorized(&'static str),
+    /// Authenticated, but the user's role doesn't allow the action.
+    Forbidden(&'static str),
+    NotFound(&'static str),
+    Conflict(String),
+    /// The `If-Match` header doesn't match the current version.
+    PreconditionFailed(&'static str),
+    /// Details are logged, not sent to the client.
+    Internal(&'static str),
+}
+
+/// A problem with one field of the request; `field` is `None` for the request as a whole.
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+struct Problem<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: &'a str,
+    status: u16,
+    detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<&'a [FieldError]>,
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::BadRequest(message)
+            | ApiError::UnsupportedMediaType(message)
+            | ApiError::PayloadTooLarge(message)
+            | ApiError::Conflict(message) => write!(f, "{}", message),
+            ApiError::Validation(_) => write!(f, "The request has invalid fields"),
+            ApiError::Unauthorized(message)
+            | ApiError::Forbidden(message)
+            | ApiError::NotFound(message)
+            | ApiError::PreconditionFailed(message)
+            | ApiError::Internal(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let errors = match self {
+            ApiError::Validation(errors) => Some(errors.as_slice()),
+            _ => None,
+        };
+        let mut response = HttpResponse::build(status);
+        if let ApiError::Unauthorized(_) = self {
+            response.insert_header(("www-authenticate", "Bearer"));
+        }
+        response
+            .content_type("application/problem+json")
+            .json(Problem {
+                kind: "about:blank",
+                title: status.canonical_reason().unwrap_or("Error"),
+                status: status.as_u16(),
+                detail: self.to_string(),
+                errors,
+            })
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = Vec::new();
+        for (field, kind) in errors.into_errors() {
+            let ValidationErrorsKind::Field(field_errors) = kind else {
+                continue;
+            };
+            for error in field_errors {
+                let message = error.message.map(|message| message.to_string()).unwrap_or_else(|| error.code.to_string());
+                // Struct-level checks are reported under `__all__`
+                let field = (field != "__all__").then(|| field.to_string());
+                fields.push(FieldError { field, message });
+            }
+        }
+        ApiError::Validation(fields)
+    }
+}
+
+impl From<JsonPayloadError> for ApiError {
+    fn from(err: JsonPayloadError) -> Self {
+        match err {
+            JsonPayloadError::ContentType => {
+                ApiError::UnsupportedMediaType("Content-Type must be application/json".to_string())
+            }
+            JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. } => {
+                ApiError::PayloadTooLarge("Request body is too large".to_string())
+            }
+            // Valid JSON of the wrong shape, e.g. a missing field or an unknown status
+            JsonPayloadError::Deserialize(err) if err.is_data() => {
+                ApiError::Validation(vec![FieldError { field: None, message: err.to_string() }])
+            }
+            err => ApiError::BadRequest(format!("Invalid JSON: {}", err)),
+        }
+    }
+}
+
+/// `JsonConfig` error handler, so body errors are problem+json like the rest of the API.
+pub fn json_error(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::from(err).into()
+}
+
+/// `QueryConfig` error handler.
+pub fn query_error(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::BadRequest(format!("Invalid query string: {}", err)).into()
+}
+
+/// `PathConfig` error handler; path parameters are all ids, so a malformed one is reported
+/// like a missing resource.
+pub fn path_error(_err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::NotFound("Not found").into()
+}
@@ -0,0 +1,123 @@
+use std::{
+    future::{ready, Ready},
+    time::Duration,
+};
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use serde_json::Value;
+use sqlx::{Postgres, Transaction};
+
+use crate::{
+    error::{ApiError, FieldError},
+    request_id::RequestId,
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Set on a response replayed for a reused `Idempotency-Key`.
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// The optional `Idempotency-Key` header, 1 to 255 visible ASCII characters.
+pub struct IdempotencyKey(pub Option<String>);
+
+impl FromRequest for IdempotencyKey {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let Some(value) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+            return ready(Ok(IdempotencyKey(None)));
+        };
+        match value.to_str() {
+            Ok(key) if (1..=255).contains(&key.len()) && key.bytes().all(|byte| byte.is_ascii_graphic()) => {
+                ready(Ok(IdempotencyKey(Some(key.to_string()))))
+            }
+            _ => ready(Err(ApiError::BadRequest(
+                "Idempotency-Key must be 1 to 255 visible ASCII characters".to_string(),
+            ))),
+        }
+    }
+}
+
+/// Claims `key` for `request` in `tx`, or returns the response recorded for it.
+///
+/// A concurrent request with the same key waits until `tx` ends, then replays its response if it
+/// was committed. Reusing a key with another request is rejected with `422`, and a key without a
+/// recorded response with `409`.
+pub async fn claim(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    key: &str,
+    request: &Value,
+    ttl: Duration,
+    request_id: &RequestId,
+) -> Result<Option<Value>, ApiError> {
+    let failed = |err| {
+        println!("[{}] {:?}", request_id.0, err);
+        ApiError::Internal("Failed to check Idempotency-Key")
+    };
+
+    sqlx::query("DELETE FROM idempotency_keys WHERE user_id = $1 AND expires_at <= CURRENT_TIMESTAMP")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(failed)?;
+
+    let claimed = sqlx::query(
+        "INSERT INTO idempotency_keys (user_id, key, request, expires_at)
+        VALUES ($1, $2, $3, CURRENT_TIMESTAMP + make_interval(secs => $4))
+        ON CONFLICT (user_id, key) DO NOTHING"
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(request)
+    .bind(ttl.as_secs_f64())
+    .execute(&mut **tx)
+    .await
+    .map_err(failed)?;
+    if claimed.rows_affected() > 0 {
+        return Ok(None);
+    }
+
+    let (used_for, response) = sqlx::query_as::<_, (Value, Option<Value>)>(
+        "SELECT request, response FROM idempotency_keys WHERE user_id = $1 AND key = $2"
+    )
+    .bind(user_id)
+    .bind(key)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(failed)?;
+    if &used_for != request {
+        return Err(ApiError::Validation(vec![FieldError {
+            field: None,
+            message: "Idempotency-Key was already used with a different request".to_string(),
+        }]));
+    }
+    // Claiming and recording happen in one transaction, so a committed key should have its response
+    match response {
+        Some(response) => Ok(Some(response)),
+        None => Err(ApiError::Conflict("A request with this Idempotency-Key is in progress".to_string())),
+    }
+}
+
+/// Records the response to send again when `key` is reused.
+pub async fn record(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    key: &str,
+    response: &Value,
+    request_id: &RequestId,
+) -> Result<(), ApiError> {
+    match sqlx::query("UPDATE idempotency_keys SET response = $3 WHERE user_id = $1 AND key = $2")
+        .bind(user_id)
+        .bind(key)
+        .bind(response)
+        .execute(&mut **tx)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to record Idempotency-Key"))
+        },
+    }
+}
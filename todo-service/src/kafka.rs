@@ -0,0 +1,50 @@
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use rdkafka::ClientConfig;
+use std::time::Duration;
+
+use crate::request_id::REQUEST_ID_HEADER;
+
+pub struct KafkaProducer {
+    /// `None` when publishing is disabled.
+    producer: Option<FutureProducer>,
+}
+
+impl KafkaProducer {
+    pub fn new(brokers: &str) -> Self {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", brokers);
+    
+        let producer: FutureProducer = config.create().expect("Failure in creating producer");
+
+        KafkaProducer { producer: Some(producer) }
+    }
+
+    /// A producer that drops every message, for running without a broker.
+    pub fn disabled() -> Self {
+        KafkaProducer { producer: None }
+    }
+
+    /// Sends `msg`, tagged with the id of the request that caused it so analytics can join them.
+    pub async fn produce(&self, topic: &str, msg: &str, request_id: &str) {
+        let Some(producer) = &self.producer else {
+            return;
+        };
+        let record = FutureRecord::to(topic)
+            .payload(msg)
+            .key("Test-Key")
+            .headers(OwnedHeaders::new().insert(Header { key: REQUEST_ID_HEADER, value: Some(request_id) }));
+    
+        let status_delivery =  producer
+            .send(record, Timeout::After(Duration::from_secs(2)))
+            .await;
+    
+        match status_delivery {
+            Ok(report) => println!("[{}] Message sent {:?}", request_id, report),
+            Err(e) => println!("[{}] Error producing.. {:?}", request_id, e),
+        }
+    }
+}
+
+
@@ -0,0 +1,155 @@
+use actix_web::{web::{self, Data, JsonConfig, PathConfig, QueryConfig}, App, HttpServer};
+use sqlx::{Pool, Postgres};
+use std::{sync::Arc, time::Duration};
+
+pub mod auth;
+use auth::Auth;
+
+pub mod authz;
+
+mod error;
+
+pub mod migrate;
+
+mod services;
+use services::{
+    create_list_todo, create_todo, delete_list_todo, delete_todo, fetch_list_todo, fetch_list_todos, fetch_todo,
+    fetch_todos, patch_list_todo, patch_todo, test_server, update_list_todo, update_todo,
+};
+
+mod header;
+
+mod idempotency;
+use header::CustomHeader;
+
+pub mod kafka;
+use kafka::KafkaProducer;
+
+mod lists;
+
+mod precondition;
+use lists::{add_member, create_list, delete_list, fetch_list, fetch_lists, fetch_members, remove_member, rename_list, update_member};
+
+mod prom;
+use prom::PrometheusMetrics;
+
+use edge_common::request_id;
+use request_id::RequestIdHeader;
+
+mod status;
+
+mod users;
+use users::{login, logout, refresh_tokens, register};
+
+/// The todo API, serving on `127.0.0.1:<port>`.
+pub struct TodoService {
+    port: u16,
+    instance: String,
+    db: Pool<Postgres>,
+    kafka_producer: Arc<KafkaProducer>,
+    auth: Arc<Auth>,
+    idempotency_ttl: Duration,
+}
+
+struct AppState {
+    /// Name of this instance, e.g. `server-1`, so replicas behind the load balancer can be told apart.
+    instance: String,
+    db: Pool<Postgres>,
+    kafka_producer: Arc<KafkaProducer>,
+    prometheus: Arc<PrometheusMetrics>,
+    auth: Arc<Auth>,
+    /// How long the response to an `Idempotency-Key` is kept.
+    idempotency_ttl: Duration,
+}
+
+impl TodoService {
+    /// The database must be migrated, see [`migrate`].
+    pub fn new(port: u16, db: Pool<Postgres>, auth: Auth) -> Self {
+        TodoService {
+            port,
+            instance: "todo-service".to_string(),
+            db,
+            kafka_producer: Arc::new(KafkaProducer::disabled()),
+            auth: Arc::new(auth),
+            idempotency_ttl: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// Names the instance in responses and metrics. Defaults to `todo-service`.
+    pub fn with_instance(mut self, instance: &str) -> Self {
+        self.instance = instance.to_string();
+        self
+    }
+
+    /// Publishes an event for every todo read or written. Events are dropped by default.
+    pub fn with_kafka_producer(mut self, kafka_producer: KafkaProducer) -> Self {
+        self.kafka_producer = Arc::new(kafka_producer);
+        self
+    }
+
+    /// How long a retry with the same `Idempotency-Key` returns the todo created by the first
+    /// request. Defaults to 24 hours.
+    pub fn with_idempotency_ttl(mut self, ttl: Duration) -> Self {
+        self.idempotency_ttl = ttl;
+        self
+    }
+
+    pub fn uri(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    pub async fn run(&self) -> std::io::Result<()> {
+        // Initialize Prometheus metrics
+        let prometheus = Arc::new(PrometheusMetrics::new(&self.instance));
+
+        let data = Data::new(AppState {
+            instance: self.instance.clone(),
+            db: self.db.clone(),
+            kafka_producer: self.kafka_producer.clone(),
+            prometheus,
+            auth: self.auth.clone(),
+            idempotency_ttl: self.idempotency_ttl,
+        });
+
+        println!("{} running on port: {}", self.instance, self.port);
+        HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .app_data(JsonConfig::default().error_handler(error::json_error))
+                .app_data(QueryConfig::default().error_handler(error::query_error))
+                .app_data(PathConfig::default().error_handler(error::path_error))
+                .wrap(CustomHeader)
+                .wrap(RequestIdHeader)
+                .service(test_server)
+                .service(register)
+                .service(login)
+                .service(refresh_tokens)
+                .service(logout)
+                .service(create_todo)
+                .service(fetch_todos)
+                .service(fetch_todo)
+                .service(update_todo)
+                .service(patch_todo)
+                .service(delete_todo)
+                .service(create_list)
+                .service(fetch_lists)
+                .service(fetch_list)
+                .service(rename_list)
+                .service(delete_list)
+                .service(fetch_members)
+                .service(add_member)
+                .service(update_member)
+                .service(remove_member)
+                .service(fetch_list_todos)
+                .service(create_list_todo)
+                .service(fetch_list_todo)
+                .service(update_list_todo)
+                .service(patch_list_todo)
+                .service(delete_list_todo)
+                .route("/metrics", web::get().to(PrometheusMetrics::metrics_handler))
+        })
+        .bind(("127.0.0.1", self.port))?
+        .run()
+        .await
+    }
+}
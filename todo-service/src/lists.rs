@@ -0,0 +1,406 @@
+use actix_web::{
+    delete, get, patch, post, put,
+    web::{Data, Json, Path},
+    HttpResponse,
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{self, FromRow, Postgres, Transaction};
+use validator::{Validate, ValidationError};
+use crate::{
+    auth::AuthUser,
+    authz::{authorize, Action, ListRole},
+    error::ApiError,
+    request_id::RequestId,
+    users::normalize,
+    AppState,
+};
+
+/// A shared list, with the role of the user asking for it.
+#[derive(Serialize, FromRow)]
+struct List {
+    id: i32,
+    name: String,
+    role: ListRole,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+#[derive(Serialize, FromRow)]
+struct Member {
+    user_id: i32,
+    email: String,
+    role: ListRole,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ListBody {
+    #[validate(custom(function = "not_blank"), length(max = 255, message = "Name must be at most 255 characters"))]
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct AddMemberBody {
+    /// Email of a registered user.
+    pub email: String,
+    pub role: ListRole,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateMemberBody {
+    pub role: ListRole,
+}
+
+fn not_blank(name: &str) -> Result<(), ValidationError> {
+    if name.trim().is_empty() {
+        return Err(ValidationError::new("blank").with_message("Name must not be empty".into()));
+    }
+    Ok(())
+}
+
+/// Logs a database error and hides it behind `message`.
+fn internal<'a>(request_id: &'a RequestId, message: &'static str) -> impl FnOnce(sqlx::Error) -> ApiError + 'a {
+    move |err| {
+        println!("[{}] {:?}", request_id.0, err);
+        ApiError::Internal(message)
+    }
+}
+
+/// Locks the members of a list and checks that `member_id` is one of them, and that giving them
+/// `role` (or removing them, with `None`) leaves the list an owner.
+async fn check_owners(
+    tx: &mut Transaction<'_, Postgres>,
+    list_id: i32,
+    member_id: i32,
+    role: Option<ListRole>,
+    request_id: &RequestId,
+) -> Result<(), ApiError> {
+    let members = sqlx::query_as::<_, (i32, ListRole)>(
+        "SELECT user_id, role FROM list_members WHERE list_id = $1 FOR UPDATE"
+    )
+    .bind(list_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(internal(request_id, "Failed to update members"))?;
+
+    let Some((_, current)) = members.iter().find(|(user_id, _)| *user_id == member_id) else {
+        return Err(ApiError::NotFound("Member not found"));
+    };
+    let owners = members.iter().filter(|(_, role)| *role == ListRole::Owner).count();
+    if *current == ListRole::Owner && role != Some(ListRole::Owner) && owners == 1 {
+        return Err(ApiError::Conflict("A list must keep at least one owner".to_string()));
+    }
+    Ok(())
+}
+
+/// Creates a list owned by the user.
+#[post("/lists")]
+pub async fn create_list(
+    state: Data<AppState>,
+    user: AuthUser,
+    body: Json<ListBody>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["POST", "/lists"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["POST", "/lists"])
+        .start_timer();
+
+    body.validate()?;
+
+    let list = sqlx::query_as::<_, List>(
+        "WITH list AS (
+            INSERT INTO lists (name) VALUES ($1) RETURNING id, name, created_at, updated_at
+        ), owner AS (
+            INSERT INTO list_members (list_id, user_id, role) SELECT id, $2, 'owner' FROM list
+        )
+        SELECT id, name, 'owner'::list_role AS role, created_at, updated_at FROM list"
+    )
+    .bind(&body.name)
+    .bind(user.id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(internal(&request_id, "Failed to create list"))?;
+
+    timer.observe_duration();
+    Ok(HttpResponse::Created().json(list))
+}
+
+/// Lists every list the user is a member of.
+#[get("/lists")]
+pub async fn fetch_lists(state: Data<AppState>, user: AuthUser, request_id: RequestId) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["GET", "/lists"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["GET", "/lists"])
+        .start_timer();
+
+    let lists = sqlx::query_as::<_, List>(
+        "SELECT lists.id, lists.name, list_members.role, lists.created_at, lists.updated_at
+        FROM lists JOIN list_members ON list_members.list_id = lists.id
+        WHERE list_members.user_id = $1
+        ORDER BY lists.id"
+    )
+    .bind(user.id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(internal(&request_id, "Failed to fetch lists"))?;
+
+    timer.observe_duration();
+    Ok(HttpResponse::Ok().json(lists))
+}
+
+#[get("/lists/{list_id}")]
+pub async fn fetch_list(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["GET", "/lists/{list_id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["GET", "/lists/{list_id}"])
+        .start_timer();
+
+    let list_id = path.into_inner();
+    let role = authorize(&state, &user, list_id, Action::View, &request_id).await?;
+
+    let list = sqlx::query_as::<_, List>(
+        "SELECT id, name, $2 AS role, created_at, updated_at FROM lists WHERE id = $1"
+    )
+    .bind(list_id)
+    .bind(role)
+    .fetch_one(&state.db)
+    .await
+    .map_err(internal(&request_id, "Failed to fetch list"))?;
+
+    timer.observe_duration();
+    Ok(HttpResponse::Ok().json(list))
+}
+
+#[patch("/lists/{list_id}")]
+pub async fn rename_list(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    body: Json<ListBody>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["PATCH", "/lists/{list_id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["PATCH", "/lists/{list_id}"])
+        .start_timer();
+
+    let list_id = path.into_inner();
+    let role = authorize(&state, &user, list_id, Action::Rename, &request_id).await?;
+    body.validate()?;
+
+    let list = sqlx::query_as::<_, List>(
+        "UPDATE lists SET name = $2 WHERE id = $1 RETURNING id, name, $3 AS role, created_at, updated_at"
+    )
+    .bind(list_id)
+    .bind(&body.name)
+    .bind(role)
+    .fetch_one(&state.db)
+    .await
+    .map_err(internal(&request_id, "Failed to rename list"))?;
+
+    timer.observe_duration();
+    Ok(HttpResponse::Ok().json(list))
+}
+
+/// Deletes a list with its todos.
+#[delete("/lists/{list_id}")]
+pub async fn delete_list(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["DELETE", "/lists/{list_id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["DELETE", "/lists/{list_id}"])
+        .start_timer();
+
+    let list_id = path.into_inner();
+    authorize(&state, &user, list_id, Action::Delete, &request_id).await?;
+
+    sqlx::query("DELETE FROM lists WHERE id = $1")
+        .bind(list_id)
+        .execute(&state.db)
+        .await
+        .map_err(internal(&request_id, "Failed to delete list"))?;
+
+    timer.observe_duration();
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[get("/lists/{list_id}/members")]
+pub async fn fetch_members(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["GET", "/lists/{list_id}/members"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["GET", "/lists/{list_id}/members"])
+        .start_timer();
+
+    let list_id = path.into_inner();
+    authorize(&state, &user, list_id, Action::View, &request_id).await?;
+
+    let members = sqlx::query_as::<_, Member>(
+        "SELECT list_members.user_id, users.email, list_members.role, list_members.created_at
+        FROM list_members JOIN users ON users.id = list_members.user_id
+        WHERE list_members.list_id = $1
+        ORDER BY list_members.created_at, list_members.user_id"
+    )
+    .bind(list_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(internal(&request_id, "Failed to fetch members"))?;
+
+    timer.observe_duration();
+    Ok(HttpResponse::Ok().json(members))
+}
+
+/// Shares a list with a registered user.
+#[post("/lists/{list_id}/members")]
+pub async fn add_member(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    body: Json<AddMemberBody>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["POST", "/lists/{list_id}/members"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["POST", "/lists/{list_id}/members"])
+        .start_timer();
+
+    let list_id = path.into_inner();
+    authorize(&state, &user, list_id, Action::ManageMembers, &request_id).await?;
+
+    match sqlx::query_as::<_, Member>(
+        "WITH member AS (
+            INSERT INTO list_members (list_id, user_id, role)
+            SELECT $1, id, $3 FROM users WHERE email = $2
+            RETURNING user_id, role, created_at
+        )
+        SELECT user_id, $2 AS email, role, created_at FROM member"
+    )
+    .bind(list_id)
+    .bind(normalize(&body.email))
+    .bind(body.role)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(member)) => {
+            timer.observe_duration();
+            Ok(HttpResponse::Created().json(member))
+        },
+        Ok(None) => Err(ApiError::NotFound("User not found")),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            Err(ApiError::Conflict("User is already a member of this list".to_string()))
+        },
+        Err(err) => Err(internal(&request_id, "Failed to add member")(err)),
+    }
+}
+
+/// Changes the role of a member. The last owner can't be demoted.
+#[put("/lists/{list_id}/members/{user_id}")]
+pub async fn update_member(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<(i32, i32)>,
+    body: Json<UpdateMemberBody>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["PUT", "/lists/{list_id}/members/{user_id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["PUT", "/lists/{list_id}/members/{user_id}"])
+        .start_timer();
+
+    let (list_id, member_id) = path.into_inner();
+    authorize(&state, &user, list_id, Action::ManageMembers, &request_id).await?;
+
+    let mut tx = state.db.begin().await.map_err(internal(&request_id, "Failed to update member"))?;
+    check_owners(&mut tx, list_id, member_id, Some(body.role), &request_id).await?;
+    let member = sqlx::query_as::<_, Member>(
+        "WITH member AS (
+            UPDATE list_members SET role = $3 WHERE list_id = $1 AND user_id = $2
+            RETURNING user_id, role, created_at
+        )
+        SELECT member.user_id, users.email, member.role, member.created_at
+        FROM member JOIN users ON users.id = member.user_id"
+    )
+    .bind(list_id)
+    .bind(member_id)
+    .bind(body.role)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal(&request_id, "Failed to update member"))?;
+    tx.commit().await.map_err(internal(&request_id, "Failed to update member"))?;
+
+    timer.observe_duration();
+    Ok(HttpResponse::Ok().json(member))
+}
+
+/// Removes a member. Owners can remove anyone, and every member can leave; the last owner can't.
+#[delete("/lists/{list_id}/members/{user_id}")]
+pub async fn remove_member(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<(i32, i32)>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["DELETE", "/lists/{list_id}/members/{user_id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["DELETE", "/lists/{list_id}/members/{user_id}"])
+        .start_timer();
+
+    let (list_id, member_id) = path.into_inner();
+    let action = if member_id == user.id { Action::View } else { Action::ManageMembers };
+    authorize(&state, &user, list_id, action, &request_id).await?;
+
+    let mut tx = state.db.begin().await.map_err(internal(&request_id, "Failed to remove member"))?;
+    check_owners(&mut tx, list_id, member_id, None, &request_id).await?;
+    sqlx::query("DELETE FROM list_members WHERE list_id = $1 AND user_id = $2")
+        .bind(list_id)
+        .bind(member_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal(&request_id, "Failed to remove member"))?;
+    tx.commit().await.map_err(internal(&request_id, "Failed to remove member"))?;
+
+    timer.observe_duration();
+    Ok(HttpResponse::NoContent().finish())
+}
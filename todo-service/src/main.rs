@@ -0,0 +1,66 @@
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+use dotenv::dotenv;
+use todo_service::{auth::Auth, kafka::KafkaProducer, migrate, TodoService};
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    dotenv().ok();
+
+    let instance = std::env::var("INSTANCE_NAME").unwrap_or("todo-service".to_string());
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&database_url)
+        .await
+        .expect("Error building a connection pool");
+
+    // `migrate` applies the pending migrations and exits
+    match std::env::args().nth(1).as_deref() {
+        None => {}
+        Some("migrate") => {
+            migrate::run(&pool).await.expect("Failed to run migrations");
+            println!("Database is up to date");
+            return Ok(());
+        }
+        Some(command) => panic!("Unknown command '{}', expected 'migrate'", command),
+    }
+
+    let run_migrations = std::env::var("RUN_MIGRATIONS").is_ok_and(|value| value == "true");
+    if run_migrations {
+        migrate::run(&pool).await.expect("Failed to run migrations");
+    }
+    match migrate::check(&pool).await {
+        Ok(0) => {}
+        Ok(pending) => eprintln!(
+            "{} pending database migration(s), run `migrate` or set RUN_MIGRATIONS=true",
+            pending
+        ),
+        Err(err) => panic!("Refusing to start, the database schema doesn't match this binary: {}", err),
+    }
+
+    let port: u16 = std::env::var("PORT")
+        .expect("PORT environment variable not set")
+        .parse()
+        .expect("PORT must be a valid integer");
+
+    let kafka_brokers = std::env::var("KAFKA_BROKERS").unwrap_or("localhost:9092".to_string());
+
+    let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+    let mut auth = Auth::new(&jwt_secret);
+    if let Ok(secs) = std::env::var("ACCESS_TOKEN_TTL_SECS") {
+        auth = auth.with_access_ttl(Duration::from_secs(secs.parse().expect("ACCESS_TOKEN_TTL_SECS must be a valid integer")));
+    }
+    if let Ok(secs) = std::env::var("REFRESH_TOKEN_TTL_SECS") {
+        auth = auth.with_refresh_ttl(Duration::from_secs(secs.parse().expect("REFRESH_TOKEN_TTL_SECS must be a valid integer")));
+    }
+
+    let mut service = TodoService::new(port, pool, auth)
+        .with_instance(&instance)
+        .with_kafka_producer(KafkaProducer::new(&kafka_brokers));
+    if let Ok(secs) = std::env::var("IDEMPOTENCY_KEY_TTL_SECS") {
+        service = service.with_idempotency_ttl(Duration::from_secs(secs.parse().expect("IDEMPOTENCY_KEY_TTL_SECS must be a valid integer")));
+    }
+    service.run().await
+}
@@ -0,0 +1,33 @@
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migrator},
+    Pool, Postgres,
+};
+
+/// The migrations in `migrations/`, embedded in the binary.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Applies the pending migrations.
+pub async fn run(pool: &Pool<Postgres>) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+/// Returns the number of pending migrations. Fails if the database has a migration this binary
+/// doesn't know, i.e. it was migrated by a newer version, or if a migration failed halfway.
+pub async fn check(pool: &Pool<Postgres>) -> Result<usize, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+
+    if let Some(version) = conn.dirty_version().await? {
+        return Err(MigrateError::Dirty(version));
+    }
+
+    let applied = conn.list_applied_migrations().await?;
+    if let Some(unknown) = applied.iter().find(|migration| !MIGRATOR.version_exists(migration.version)) {
+        return Err(MigrateError::VersionMissing(unknown.version));
+    }
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| !applied.iter().any(|done| done.version == migration.version))
+        .count())
+}
@@ -0,0 +1,69 @@
+use std::future::{ready, Ready};
+use actix_web::{
+    dev::Payload,
+    http::header::{EntityTag, Header, IfMatch, IfNoneMatch, ETag},
+    FromRequest, HttpRequest,
+};
+
+use crate::error::ApiError;
+
+/// The ETag of a todo version, e.g. `"3"`.
+pub fn etag(version: i32) -> ETag {
+    ETag(EntityTag::new_strong(version.to_string()))
+}
+
+/// The `If-Match` and `If-None-Match` headers of a request, both optional.
+pub struct Preconditions {
+    if_match: Option<IfMatch>,
+    if_none_match: Option<IfNoneMatch>,
+}
+
+impl Preconditions {
+    /// Versions a write must apply to, from `If-Match`; `None` when any version will do.
+    /// Weak tags never match, as `If-Match` uses the strong comparison.
+    pub fn versions(&self) -> Option<Vec<i32>> {
+        match &self.if_match {
+            Some(IfMatch::Items(tags)) => Some(
+                tags.iter()
+                    .filter(|tag| !tag.weak)
+                    .filter_map(|tag| tag.tag().parse().ok())
+                    .collect(),
+            ),
+            Some(IfMatch::Any) | None => None,
+        }
+    }
+
+    /// Whether a read of `version` can be answered with `304 Not Modified`.
+    pub fn not_modified(&self, version: i32) -> bool {
+        match &self.if_none_match {
+            Some(IfNoneMatch::Any) => true,
+            Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag(version).0)),
+            None => false,
+        }
+    }
+}
+
+impl FromRequest for Preconditions {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let if_match = if req.headers().contains_key(IfMatch::name()) {
+            match IfMatch::parse(req) {
+                Ok(header) => Some(header),
+                Err(_) => return ready(Err(ApiError::BadRequest("Invalid If-Match header".to_string()))),
+            }
+        } else {
+            None
+        };
+        let if_none_match = if req.headers().contains_key(IfNoneMatch::name()) {
+            match IfNoneMatch::parse(req) {
+                Ok(header) => Some(header),
+                Err(_) => return ready(Err(ApiError::BadRequest("Invalid If-None-Match header".to_string()))),
+            }
+        } else {
+            None
+        };
+        ready(Ok(Preconditions { if_match, if_none_match }))
+    }
+}
@@ -1,5 +1,5 @@
 use prometheus::{Encoder, HistogramVec, IntCounterVec, Registry, TextEncoder};
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use actix_web::{HttpResponse, Responder, web::Data};
 
 use crate::AppState;
@@ -11,9 +11,11 @@ pub struct PrometheusMetrics {
 }
 
 impl PrometheusMetrics {
-    pub fn new() -> Self {
+    /// Every metric is labelled with the `server` instance name.
+    pub fn new(instance: &str) -> Self {
         // Create a Prometheus registry
-        let registry = Registry::new_custom(Some("actix_web".to_string()), None).unwrap();
+        let labels = HashMap::from([("server".to_string(), instance.to_string())]);
+        let registry = Registry::new_custom(Some("actix_web".to_string()), Some(labels)).unwrap();
 
         // Create HTTP request metrics
         let http_requests_total = IntCounterVec::new(
@@ -0,0 +1,844 @@
+use actix_web::{
+    delete, get, patch, post, put,
+    http::header::{HeaderName, HeaderValue},
+    web::{Data, Json, Path, Query},
+    Responder, HttpResponse
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::{self, FromRow, PgExecutor, Postgres, QueryBuilder};
+use validator::{Validate, ValidationError};
+use crate::{
+    auth::AuthUser,
+    authz::{authorize, Action},
+    error::ApiError,
+    idempotency::{self, IdempotencyKey, REPLAYED_HEADER},
+    precondition::{etag, Preconditions},
+    request_id::RequestId,
+    status::TodoStatus,
+    AppState,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+#[derive(Serialize, Deserialize, FromRow)]
+struct Todo {
+    id: i32,
+    title: String,
+    description: Option<String>,       // Description can be NULL in the schema
+    status: TodoStatus,
+    due_date: Option<NaiveDate>,       // Date can be NULL
+    created_at: NaiveDateTime,         // Timestamp for creation
+    updated_at: NaiveDateTime,         // Timestamp for last update
+    version: i32,                      // Bumped on every update, sent as the ETag
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct CreateTodoBody {
+    #[validate(custom(function = "not_blank"), length(max = 255, message = "Title must be at most 255 characters"))]
+    pub title: String,
+    pub description: Option<String>,   // Accepting description field for task creation
+    pub status: Option<TodoStatus>,    // Allow users to provide a status (optional)
+    pub due_date: Option<NaiveDate>,   // Due date can be optional
+}
+
+#[derive(Deserialize, Validate)]
+pub struct UpdateTodoBody {
+    #[validate(custom(function = "not_blank"), length(max = 255, message = "Title must be at most 255 characters"))]
+    pub title: String,
+    pub description: Option<String>,
+    pub status: TodoStatus,
+    pub due_date: Option<NaiveDate>,
+}
+
+/// Fields left out are kept; `description` and `due_date` can be cleared with `null`.
+#[derive(Deserialize, Validate)]
+#[validate(schema(function = "has_changes"))]
+pub struct PatchTodoBody {
+    #[validate(custom(function = "not_blank"), length(max = 255, message = "Title must be at most 255 characters"))]
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub description: Option<Option<String>>,
+    pub status: Option<TodoStatus>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub due_date: Option<Option<NaiveDate>>,
+}
+
+/// Query string of `GET /todos`.
+#[derive(Deserialize)]
+pub struct ListTodosQuery {
+    /// Page size, 1 to 100 (default 20).
+    pub limit: Option<i64>,
+    /// Rows to skip, for offset pagination.
+    pub offset: Option<i64>,
+    /// `next_cursor` of the previous page, for keyset pagination.
+    pub cursor: Option<String>,
+    pub status: Option<TodoStatus>,
+    /// Only todos due on or after this date.
+    pub due_after: Option<NaiveDate>,
+    /// Only todos due on or before this date.
+    pub due_before: Option<NaiveDate>,
+    /// Case-insensitive search in the title.
+    pub q: Option<String>,
+    /// Field to sort by, prefixed with `-` for descending (default `created_at`).
+    pub sort: Option<String>,
+}
+
+/// A page of todos. `total` counts every todo matching the filters.
+#[derive(Serialize)]
+struct TodoPage {
+    items: Vec<Todo>,
+    total: i64,
+    next_cursor: Option<String>,
+}
+
+struct Sort {
+    column: &'static str,
+    descending: bool,
+}
+
+impl Sort {
+    fn parse(value: &str) -> Option<Self> {
+        let (descending, field) = match value.strip_prefix('-') {
+            Some(field) => (true, field),
+            None => (false, value),
+        };
+        let column = match field {
+            "id" => "id",
+            "title" => "title",
+            "status" => "status",
+            "due_date" => "due_date",
+            "created_at" => "created_at",
+            "updated_at" => "updated_at",
+            _ => return None,
+        };
+        Some(Sort { column, descending })
+    }
+
+    /// Cursors are only supported on the columns they are built from.
+    fn supports_cursor(&self) -> bool {
+        matches!(self.column, "id" | "created_at")
+    }
+}
+
+/// Position after the last todo of a page, sent as `<created_at in microseconds>_<id>`.
+struct Cursor {
+    created_at: NaiveDateTime,
+    id: i32,
+}
+
+impl Cursor {
+    fn after(todo: &Todo) -> Self {
+        Cursor { created_at: todo.created_at, id: todo.id }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let (micros, id) = value.split_once('_')?;
+        let created_at = DateTime::from_timestamp_micros(micros.parse().ok()?)?.naive_utc();
+        Some(Cursor { created_at, id: id.parse().ok()? })
+    }
+
+    fn encode(&self) -> String {
+        format!("{}_{}", self.created_at.and_utc().timestamp_micros(), self.id)
+    }
+}
+
+/// Whose todos a request works on.
+#[derive(Clone, Copy)]
+enum Scope {
+    /// The personal todos of a user, outside any list.
+    User(i32),
+    /// The todos of a shared list, whoever created them.
+    List(i32),
+}
+
+impl Scope {
+    /// SQL condition matching the todos of the scope, with its id bound as `$n`.
+    fn condition(self, n: usize) -> String {
+        match self {
+            Scope::User(_) => format!("user_id = ${} AND list_id IS NULL", n),
+            Scope::List(_) => format!("list_id = ${}", n),
+        }
+    }
+
+    fn id(self) -> i32 {
+        match self {
+            Scope::User(id) | Scope::List(id) => id,
+        }
+    }
+
+    fn list_id(self) -> Option<i32> {
+        match self {
+            Scope::User(_) => None,
+            Scope::List(id) => Some(id),
+        }
+    }
+}
+
+fn push_filters(builder: &mut QueryBuilder<Postgres>, query: &ListTodosQuery, scope: Scope) {
+    match scope {
+        Scope::User(user_id) => builder.push(" WHERE user_id = ").push_bind(user_id).push(" AND list_id IS NULL"),
+        Scope::List(list_id) => builder.push(" WHERE list_id = ").push_bind(list_id),
+    };
+    if let Some(status) = query.status {
+        builder.push(" AND status = ").push_bind(status);
+    }
+    if let Some(due_after) = query.due_after {
+        builder.push(" AND due_date >= ").push_bind(due_after);
+    }
+    if let Some(due_before) = query.due_before {
+        builder.push(" AND due_date <= ").push_bind(due_before);
+    }
+    if let Some(q) = &query.q {
+        let pattern = q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        builder.push(" AND title ILIKE ").push_bind(format!("%{}%", pattern));
+    }
+}
+
+/// Tells a field set to `null` (`Some(None)`) apart from a missing one (`None`).
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+fn not_blank(title: &str) -> Result<(), ValidationError> {
+    if title.trim().is_empty() {
+        return Err(ValidationError::new("blank").with_message("Title must not be empty".into()));
+    }
+    Ok(())
+}
+
+fn has_changes(body: &PatchTodoBody) -> Result<(), ValidationError> {
+    if body.title.is_none() && body.description.is_none() && body.status.is_none() && body.due_date.is_none() {
+        return Err(ValidationError::new("empty").with_message("No fields to update".into()));
+    }
+    Ok(())
+}
+
+fn bad_request(message: &str) -> ApiError {
+    ApiError::BadRequest(message.to_string())
+}
+
+/// Tells why a write guarded by `If-Match` and a status transition matched no todo: it
+/// doesn't exist, it has another version, or it can't move from its current status to `next`.
+async fn write_failure(
+    state: &AppState,
+    id: i32,
+    scope: Scope,
+    next: Option<TodoStatus>,
+    versions: Option<&[i32]>,
+    request_id: &RequestId,
+) -> ApiError {
+    match sqlx::query_as::<_, (TodoStatus, i32)>(&format!(
+        "SELECT status, version FROM todo WHERE id = $1 AND {}",
+        scope.condition(2)
+    ))
+    .bind(id)
+    .bind(scope.id())
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(None) => ApiError::NotFound("Todo not found"),
+        Ok(Some((_, version))) if versions.is_some_and(|versions| !versions.contains(&version)) => {
+            ApiError::PreconditionFailed("Todo has changed, fetch it again for its current ETag")
+        },
+        Ok(Some((current, _))) => match next {
+            Some(next) => {
+                let allowed: Vec<&str> = current.targets().into_iter().map(TodoStatus::as_str).collect();
+                ApiError::Conflict(format!(
+                    "Can't move a todo from {} to {}, allowed: {}",
+                    current.as_str(),
+                    next.as_str(),
+                    if allowed.is_empty() { "none".to_string() } else { allowed.join(", ") },
+                ))
+            },
+            // It matched by the time of this check, so it changed in between
+            None => ApiError::Conflict("Todo was changed concurrently, try again".to_string()),
+        },
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            ApiError::Internal("Failed to update Todo")
+        },
+    }
+}
+
+/// A todo with its version as the ETag.
+fn todo_response(todo: Todo) -> HttpResponse {
+    HttpResponse::Ok().insert_header(etag(todo.version)).json(todo)
+}
+
+async fn find_todos(
+    state: &AppState,
+    scope: Scope,
+    query: &ListTodosQuery,
+    request_id: &RequestId,
+) -> Result<TodoPage, ApiError> {
+    let limit = query.limit.unwrap_or(20);
+    if !(1..=100).contains(&limit) {
+        return Err(bad_request("limit must be between 1 and 100"));
+    }
+    if query.offset.is_some_and(|offset| offset < 0) {
+        return Err(bad_request("offset must not be negative"));
+    }
+    let Some(sort) = Sort::parse(query.sort.as_deref().unwrap_or("created_at")) else {
+        return Err(bad_request("sort must be one of id, title, status, due_date, created_at, updated_at, optionally prefixed with -"));
+    };
+    let cursor = match &query.cursor {
+        None => None,
+        Some(_) if query.offset.is_some() => return Err(bad_request("cursor and offset can't be combined")),
+        Some(_) if !sort.supports_cursor() => return Err(bad_request("cursor is only supported when sorting by id or created_at")),
+        Some(value) => match Cursor::parse(value) {
+            Some(cursor) => Some(cursor),
+            None => return Err(bad_request("Invalid cursor")),
+        },
+    };
+
+    let kafka_producer = &state.kafka_producer;
+    kafka_producer.produce("test-topic", "fetch", &request_id.0).await;
+
+    let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM todo");
+    push_filters(&mut count, query, scope);
+    let total = match count.build_query_scalar::<i64>().fetch_one(&state.db).await {
+        Ok(total) => total,
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            return Err(ApiError::Internal("Failed to fetch Todos"));
+        },
+    };
+
+    let direction = if sort.descending { "DESC" } else { "ASC" };
+    let mut select = QueryBuilder::<Postgres>::new(
+        "SELECT id, title, description, status, due_date, created_at, updated_at, version FROM todo"
+    );
+    push_filters(&mut select, query, scope);
+    if let Some(cursor) = &cursor {
+        let comparison = if sort.descending { "<" } else { ">" };
+        if sort.column == "id" {
+            select.push(format!(" AND id {} ", comparison)).push_bind(cursor.id);
+        } else {
+            select.push(format!(" AND (created_at, id) {} (", comparison))
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+    }
+    select.push(format!(" ORDER BY {} {}", sort.column, direction));
+    if sort.column == "due_date" {
+        select.push(" NULLS LAST");
+    }
+    if sort.column != "id" {
+        // Ties are broken by id so pages don't overlap
+        select.push(format!(", id {}", direction));
+    }
+    // One extra row tells whether there is a next page
+    select.push(" LIMIT ").push_bind(limit + 1);
+    if let Some(offset) = query.offset {
+        select.push(" OFFSET ").push_bind(offset);
+    }
+
+    match select.build_query_as::<Todo>().fetch_all(&state.db).await {
+        Ok(mut items) => {
+            let has_more = items.len() as i64 > limit;
+            items.truncate(limit as usize);
+            let next_cursor = if has_more && sort.supports_cursor() {
+                items.last().map(|todo| Cursor::after(todo).encode())
+            } else {
+                None
+            };
+            Ok(TodoPage { items, total, next_cursor })
+        },
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to fetch Todos"))
+        },
+    }
+}
+
+/// Creates a todo in `scope` through `db`, the pool or a transaction, recording `user` as its creator.
+async fn insert_todo(
+    db: impl PgExecutor<'_>,
+    scope: Scope,
+    user: &AuthUser,
+    body: &CreateTodoBody,
+    request_id: &RequestId,
+) -> Result<Todo, ApiError> {
+    body.validate()?;
+
+    let status = body.status.unwrap_or(TodoStatus::Pending);
+
+    match sqlx::query_as::<_, Todo>(
+        "INSERT INTO todo (title, description, status, due_date, user_id, list_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, title, description, status, due_date, created_at, updated_at, version"
+    )
+    .bind(&body.title)
+    .bind(&body.description)
+    .bind(status)
+    .bind(body.due_date)
+    .bind(user.id)
+    .bind(scope.list_id())
+    .fetch_one(db)
+    .await
+    {
+        Ok(todo) => Ok(todo),
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to create Todo"))
+        },
+    }
+}
+
+async fn find_todo(state: &AppState, scope: Scope, id: i32, request_id: &RequestId) -> Result<Todo, ApiError> {
+    match sqlx::query_as::<_, Todo>(&format!(
+        "SELECT id, title, description, status, due_date, created_at, updated_at, version FROM todo WHERE id = $1 AND {}",
+        scope.condition(2)
+    ))
+    .bind(id)
+    .bind(scope.id())
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(todo)) => {
+            state.kafka_producer.produce("test-topic", "fetch", &request_id.0).await;
+            Ok(todo)
+        },
+        Ok(None) => Err(ApiError::NotFound("Todo not found")),
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to fetch Todo"))
+        },
+    }
+}
+
+/// Replaces a todo; with `versions`, only if it has one of them.
+async fn replace_todo(
+    state: &AppState,
+    scope: Scope,
+    id: i32,
+    body: &UpdateTodoBody,
+    versions: Option<Vec<i32>>,
+    request_id: &RequestId,
+) -> Result<Todo, ApiError> {
+    body.validate()?;
+
+    match sqlx::query_as::<_, Todo>(&format!(
+        "UPDATE todo SET title = $2, description = $3, status = $4, due_date = $5
+        WHERE id = $1 AND status = ANY($6) AND ($8::INTEGER[] IS NULL OR version = ANY($8)) AND {}
+        RETURNING id, title, description, status, due_date, created_at, updated_at, version",
+        scope.condition(7)
+    ))
+    .bind(id)
+    .bind(&body.title)
+    .bind(&body.description)
+    .bind(body.status)
+    .bind(body.due_date)
+    .bind(TodoStatus::sources(body.status))
+    .bind(scope.id())
+    .bind(&versions)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(todo)) => {
+            state.kafka_producer.produce("test-topic", "update", &request_id.0).await;
+            Ok(todo)
+        },
+        Ok(None) => Err(write_failure(state, id, scope, Some(body.status), versions.as_deref(), request_id).await),
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to update Todo"))
+        },
+    }
+}
+
+/// Updates the fields of a todo present in `body`; with `versions`, only if it has one of them.
+async fn modify_todo(
+    state: &AppState,
+    scope: Scope,
+    id: i32,
+    body: &PatchTodoBody,
+    versions: Option<Vec<i32>>,
+    request_id: &RequestId,
+) -> Result<Todo, ApiError> {
+    body.validate()?;
+
+    match sqlx::query_as::<_, Todo>(&format!(
+        "UPDATE todo SET
+            title = COALESCE($2, title),
+            description = CASE WHEN $3 THEN $4 ELSE description END,
+            status = COALESCE($5, status),
+            due_date = CASE WHEN $6 THEN $7 ELSE due_date END
+        WHERE id = $1 AND status = ANY($8) AND ($10::INTEGER[] IS NULL OR version = ANY($10)) AND {}
+        RETURNING id, title, description, status, due_date, created_at, updated_at, version",
+        scope.condition(9)
+    ))
+    .bind(id)
+    .bind(&body.title)
+    .bind(body.description.is_some())
+    .bind(body.description.clone().flatten())
+    .bind(body.status)
+    .bind(body.due_date.is_some())
+    .bind(body.due_date.flatten())
+    .bind(body.status.map_or(TodoStatus::ALL.to_vec(), TodoStatus::sources))
+    .bind(scope.id())
+    .bind(&versions)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(todo)) => {
+            state.kafka_producer.produce("test-topic", "patch", &request_id.0).await;
+            Ok(todo)
+        },
+        Ok(None) => Err(write_failure(state, id, scope, body.status, versions.as_deref(), request_id).await),
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to update Todo"))
+        },
+    }
+}
+
+/// Deletes a todo; with `versions`, only if it has one of them.
+async fn remove_todo(
+    state: &AppState,
+    scope: Scope,
+    id: i32,
+    versions: Option<Vec<i32>>,
+    request_id: &RequestId,
+) -> Result<(), ApiError> {
+    match sqlx::query(&format!(
+        "DELETE FROM todo WHERE id = $1 AND ($3::INTEGER[] IS NULL OR version = ANY($3)) AND {}",
+        scope.condition(2)
+    ))
+    .bind(id)
+    .bind(scope.id())
+    .bind(&versions)
+    .execute(&state.db)
+    .await
+    {
+        Ok(result) if result.rows_affected() > 0 => {
+            state.kafka_producer.produce("test-topic", "delete", &request_id.0).await;
+            Ok(())
+        },
+        Ok(_) => Err(write_failure(state, id, scope, None, versions.as_deref(), request_id).await),
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to delete Todo"))
+        },
+    }
+}
+
+#[get("/")]
+pub async fn test_server(state: Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(format!("{} is running", state.instance))
+}
+
+/// Lists the user's personal todos a page at a time, with offset or cursor pagination,
+/// filters and sorting.
+#[get("/todos")]
+pub async fn fetch_todos(
+    state: Data<AppState>,
+    user: AuthUser,
+    query: Query<ListTodosQuery>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["GET", "/todos"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["GET", "/todos"])
+        .start_timer();
+
+    let page = find_todos(&state, Scope::User(user.id), &query, &request_id).await?;
+    timer.observe_duration();
+    Ok(HttpResponse::Ok().json(page))
+}
+
+/// With an `Idempotency-Key`, a retry of the request returns the todo it created instead of a new one.
+#[post("/todo")]
+pub async fn create_todo(
+    state: Data<AppState>,
+    user: AuthUser,
+    body: Json<CreateTodoBody>,
+    idempotency_key: IdempotencyKey,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["POST", "/todos"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["POST", "/todos"])
+        .start_timer();
+
+    let Some(key) = idempotency_key.0 else {
+        let todo = insert_todo(&state.db, Scope::User(user.id), &user, &body, &request_id).await?;
+        state.kafka_producer.produce("test-topic", "create", &request_id.0).await;
+        timer.observe_duration();
+        return Ok(todo_response(todo));
+    };
+
+    let failed = |err| {
+        println!("[{}] {:?}", request_id.0, err);
+        ApiError::Internal("Failed to create Todo")
+    };
+    let request = serde_json::to_value(&*body).expect("Failed to serialize request");
+    let mut tx = state.db.begin().await.map_err(failed)?;
+    if let Some(response) = idempotency::claim(&mut tx, user.id, &key, &request, state.idempotency_ttl, &request_id).await? {
+        // Nothing is created again, so no event either
+        let todo: Todo = serde_json::from_value(response).map_err(|err| {
+            println!("[{}] Invalid recorded response: {:?}", request_id.0, err);
+            ApiError::Internal("Failed to create Todo")
+        })?;
+        let mut response = todo_response(todo);
+        response.headers_mut().insert(HeaderName::from_static(REPLAYED_HEADER), HeaderValue::from_static("true"));
+        timer.observe_duration();
+        return Ok(response);
+    }
+    let todo = insert_todo(&mut *tx, Scope::User(user.id), &user, &body, &request_id).await?;
+    let response = serde_json::to_value(&todo).expect("Failed to serialize Todo");
+    idempotency::record(&mut tx, user.id, &key, &response, &request_id).await?;
+    tx.commit().await.map_err(failed)?;
+    state.kafka_producer.produce("test-topic", "create", &request_id.0).await;
+
+    timer.observe_duration();
+    Ok(todo_response(todo))
+}
+
+/// Answers `304 Not Modified` when `If-None-Match` has the current ETag.
+#[get("/todos/{id}")]
+pub async fn fetch_todo(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    preconditions: Preconditions,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["GET", "/todos/{id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["GET", "/todos/{id}"])
+        .start_timer();
+
+    let todo = find_todo(&state, Scope::User(user.id), path.into_inner(), &request_id).await?;
+    timer.observe_duration();
+    if preconditions.not_modified(todo.version) {
+        return Ok(HttpResponse::NotModified().insert_header(etag(todo.version)).finish());
+    }
+    Ok(todo_response(todo))
+}
+
+/// Replaces every field of a todo. With `If-Match`, only if the todo still has that ETag.
+#[put("/todos/{id}")]
+pub async fn update_todo(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    body: Json<UpdateTodoBody>,
+    preconditions: Preconditions,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["PUT", "/todos/{id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["PUT", "/todos/{id}"])
+        .start_timer();
+
+    let todo = replace_todo(&state, Scope::User(user.id), path.into_inner(), &body, preconditions.versions(), &request_id).await?;
+    timer.observe_duration();
+    Ok(todo_response(todo))
+}
+
+/// Updates only the fields present in the body. With `If-Match`, only if the todo still has that ETag.
+#[patch("/todos/{id}")]
+pub async fn patch_todo(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    body: Json<PatchTodoBody>,
+    preconditions: Preconditions,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["PATCH", "/todos/{id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["PATCH", "/todos/{id}"])
+        .start_timer();
+
+    let todo = modify_todo(&state, Scope::User(user.id), path.into_inner(), &body, preconditions.versions(), &request_id).await?;
+    timer.observe_duration();
+    Ok(todo_response(todo))
+}
+
+#[delete("/todos/{id}")]
+pub async fn delete_todo(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    preconditions: Preconditions,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["DELETE", "/todos/{id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["DELETE", "/todos/{id}"])
+        .start_timer();
+
+    remove_todo(&state, Scope::User(user.id), path.into_inner(), preconditions.versions(), &request_id).await?;
+    timer.observe_duration();
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Lists the todos of a shared list, with the same query parameters as `GET /todos`.
+#[get("/lists/{list_id}/todos")]
+pub async fn fetch_list_todos(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    query: Query<ListTodosQuery>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["GET", "/lists/{list_id}/todos"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["GET", "/lists/{list_id}/todos"])
+        .start_timer();
+
+    let list_id = path.into_inner();
+    authorize(&state, &user, list_id, Action::View, &request_id).await?;
+    let page = find_todos(&state, Scope::List(list_id), &query, &request_id).await?;
+    timer.observe_duration();
+    Ok(HttpResponse::Ok().json(page))
+}
+
+#[post("/lists/{list_id}/todos")]
+pub async fn create_list_todo(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<i32>,
+    body: Json<CreateTodoBody>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["POST", "/lists/{list_id}/todos"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["POST", "/lists/{list_id}/todos"])
+        .start_timer();
+
+    let list_id = path.into_inner();
+    authorize(&state, &user, list_id, Action::EditTodos, &request_id).await?;
+    let todo = insert_todo(&state.db, Scope::List(list_id), &user, &body, &request_id).await?;
+    state.kafka_producer.produce("test-topic", "create", &request_id.0).await;
+    timer.observe_duration();
+    Ok(todo_response(todo))
+}
+
+#[get("/lists/{list_id}/todos/{id}")]
+pub async fn fetch_list_todo(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<(i32, i32)>,
+    preconditions: Preconditions,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["GET", "/lists/{list_id}/todos/{id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["GET", "/lists/{list_id}/todos/{id}"])
+        .start_timer();
+
+    let (list_id, id) = path.into_inner();
+    authorize(&state, &user, list_id, Action::View, &request_id).await?;
+    let todo = find_todo(&state, Scope::List(list_id), id, &request_id).await?;
+    timer.observe_duration();
+    if preconditions.not_modified(todo.version) {
+        return Ok(HttpResponse::NotModified().insert_header(etag(todo.version)).finish());
+    }
+    Ok(todo_response(todo))
+}
+
+#[put("/lists/{list_id}/todos/{id}")]
+pub async fn update_list_todo(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<(i32, i32)>,
+    body: Json<UpdateTodoBody>,
+    preconditions: Preconditions,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["PUT", "/lists/{list_id}/todos/{id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["PUT", "/lists/{list_id}/todos/{id}"])
+        .start_timer();
+
+    let (list_id, id) = path.into_inner();
+    authorize(&state, &user, list_id, Action::EditTodos, &request_id).await?;
+    let todo = replace_todo(&state, Scope::List(list_id), id, &body, preconditions.versions(), &request_id).await?;
+    timer.observe_duration();
+    Ok(todo_response(todo))
+}
+
+#[patch("/lists/{list_id}/todos/{id}")]
+pub async fn patch_list_todo(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<(i32, i32)>,
+    body: Json<PatchTodoBody>,
+    preconditions: Preconditions,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["PATCH", "/lists/{list_id}/todos/{id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["PATCH", "/lists/{list_id}/todos/{id}"])
+        .start_timer();
+
+    let (list_id, id) = path.into_inner();
+    authorize(&state, &user, list_id, Action::EditTodos, &request_id).await?;
+    let todo = modify_todo(&state, Scope::List(list_id), id, &body, preconditions.versions(), &request_id).await?;
+    timer.observe_duration();
+    Ok(todo_response(todo))
+}
+
+#[delete("/lists/{list_id}/todos/{id}")]
+pub async fn delete_list_todo(
+    state: Data<AppState>,
+    user: AuthUser,
+    path: Path<(i32, i32)>,
+    preconditions: Preconditions,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["DELETE", "/lists/{list_id}/todos/{id}"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["DELETE", "/lists/{list_id}/todos/{id}"])
+        .start_timer();
+
+    let (list_id, id) = path.into_inner();
+    authorize(&state, &user, list_id, Action::EditTodos, &request_id).await?;
+    remove_todo(&state, Scope::List(list_id), id, preconditions.versions(), &request_id).await?;
+    timer.observe_duration();
+    Ok(HttpResponse::NoContent().finish())
+}
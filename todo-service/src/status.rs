@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+/// Lifecycle of a todo, stored as the Postgres enum `todo_status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "todo_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    Pending,
+    InProgress,
+    Completed,
+    Archived,
+}
+
+impl TodoStatus {
+    pub const ALL: [TodoStatus; 4] =
+        [TodoStatus::Pending, TodoStatus::InProgress, TodoStatus::Completed, TodoStatus::Archived];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TodoStatus::Pending => "pending",
+            TodoStatus::InProgress => "in_progress",
+            TodoStatus::Completed => "completed",
+            TodoStatus::Archived => "archived",
+        }
+    }
+
+    /// Allowed moves: work can be started, paused, finished or reopened, and anything can be
+    /// archived. Archived todos are final. Keeping the same status is always allowed.
+    pub fn can_transition_to(self, next: TodoStatus) -> bool {
+        use TodoStatus::*;
+        self == next
+            || matches!(
+                (self, next),
+                (Pending, InProgress | Completed | Archived)
+                    | (InProgress, Pending | Completed | Archived)
+                    | (Completed, InProgress | Archived)
+            )
+    }
+
+    /// Other statuses a todo in this status may move to.
+    pub fn targets(self) -> Vec<TodoStatus> {
+        TodoStatus::ALL.into_iter().filter(|&next| next != self && self.can_transition_to(next)).collect()
+    }
+
+    /// Statuses a todo may be in to move to `next`.
+    pub fn sources(next: TodoStatus) -> Vec<TodoStatus> {
+        TodoStatus::ALL.into_iter().filter(|status| status.can_transition_to(next)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TodoStatus::{self, *};
+
+    #[test]
+    fn transition_table() {
+        let allowed: [(TodoStatus, &[TodoStatus]); 4] = [
+            (Pending, &[Pending, InProgress, Completed, Archived]),
+            (InProgress, &[Pending, InProgress, Completed, Archived]),
+            (Completed, &[InProgress, Completed, Archived]),
+            (Archived, &[Archived]),
+        ];
+        for (from, targets) in allowed {
+            for to in TodoStatus::ALL {
+                assert_eq!(from.can_transition_to(to), targets.contains(&to), "{:?} -> {:?}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn targets_and_sources_leave_out_the_same_status() {
+        assert_eq!(Completed.targets(), [InProgress, Archived]);
+        assert!(Archived.targets().is_empty());
+        assert_eq!(TodoStatus::sources(Pending), [Pending, InProgress]);
+        assert_eq!(TodoStatus::sources(Archived), TodoStatus::ALL);
+    }
+}
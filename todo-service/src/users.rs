@@ -0,0 +1,221 @@
+use actix_web::{
+    post,
+    web::{self, Data, Json},
+    HttpResponse,
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{self, FromRow};
+use validator::Validate;
+use crate::{
+    auth::{hash_password, verify_password, TokenKind, DUMMY_PASSWORD_HASH},
+    error::ApiError,
+    request_id::RequestId,
+    AppState,
+};
+
+#[derive(Serialize, FromRow)]
+struct User {
+    id: i32,
+    email: String,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct RegisterBody {
+    #[validate(email(message = "Email must be a valid address"), length(max = 255, message = "Email must be at most 255 characters"))]
+    pub email: String,
+    #[validate(length(min = 8, max = 128, message = "Password must be 8 to 128 characters"))]
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct LoginBody {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct RefreshBody {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize)]
+struct TokenPair {
+    access_token: String,
+    refresh_token: String,
+    token_type: &'static str,
+    /// Lifetime of the access token in seconds.
+    expires_in: u64,
+}
+
+/// Emails are matched case-insensitively.
+pub fn normalize(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Issues an access token and a refresh token, and records the refresh token so it can be used once.
+async fn issue_tokens(state: &AppState, user_id: i32, request_id: &RequestId) -> Result<TokenPair, ApiError> {
+    let access = state.auth.issue(user_id, TokenKind::Access);
+    let refresh = state.auth.issue(user_id, TokenKind::Refresh);
+
+    match sqlx::query(
+        "INSERT INTO refresh_tokens (jti, user_id, expires_at)
+        VALUES ($1, $2, CURRENT_TIMESTAMP + make_interval(secs => $3))"
+    )
+    .bind(&refresh.jti)
+    .bind(user_id)
+    .bind(refresh.ttl.as_secs_f64())
+    .execute(&state.db)
+    .await
+    {
+        Ok(_) => Ok(TokenPair {
+            access_token: access.token,
+            refresh_token: refresh.token,
+            token_type: "Bearer",
+            expires_in: access.ttl.as_secs(),
+        }),
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to issue tokens"))
+        },
+    }
+}
+
+#[post("/auth/register")]
+pub async fn register(
+    state: Data<AppState>,
+    body: Json<RegisterBody>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["POST", "/auth/register"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["POST", "/auth/register"])
+        .start_timer();
+
+    body.validate()?;
+
+    // Hashing is deliberately slow, keep it off the async workers
+    let password = body.password.clone();
+    let password_hash = web::block(move || hash_password(&password))
+        .await
+        .map_err(|_| ApiError::Internal("Failed to register user"))?;
+
+    match sqlx::query_as::<_, User>(
+        "INSERT INTO users (email, password_hash) VALUES ($1, $2) RETURNING id, email, created_at"
+    )
+    .bind(normalize(&body.email))
+    .bind(&password_hash)
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(user) => {
+            timer.observe_duration();
+            Ok(HttpResponse::Created().json(user))
+        },
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            Err(ApiError::Conflict("Email is already registered".to_string()))
+        },
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to register user"))
+        },
+    }
+}
+
+#[post("/auth/login")]
+pub async fn login(state: Data<AppState>, body: Json<LoginBody>, request_id: RequestId) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["POST", "/auth/login"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["POST", "/auth/login"])
+        .start_timer();
+
+    let user = match sqlx::query_as::<_, (i32, String)>("SELECT id, password_hash FROM users WHERE email = $1")
+        .bind(normalize(&body.email))
+        .fetch_optional(&state.db)
+        .await
+    {
+        Ok(user) => user,
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            return Err(ApiError::Internal("Failed to log in"));
+        },
+    };
+    // Unknown emails are still hashed, so the response time doesn't tell which emails exist
+    let (user_id, password_hash) = match user {
+        Some((user_id, password_hash)) => (Some(user_id), password_hash),
+        None => (None, DUMMY_PASSWORD_HASH.clone()),
+    };
+
+    let password = body.password.clone();
+    let valid = web::block(move || verify_password(&password, &password_hash))
+        .await
+        .map_err(|_| ApiError::Internal("Failed to log in"))?;
+    let Some(user_id) = user_id.filter(|_| valid) else {
+        return Err(ApiError::Unauthorized("Invalid email or password"));
+    };
+
+    let tokens = issue_tokens(&state, user_id, &request_id).await?;
+    timer.observe_duration();
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+/// Exchanges a refresh token for a new pair. The old refresh token can't be used again.
+#[post("/auth/refresh")]
+pub async fn refresh_tokens(state: Data<AppState>, body: Json<RefreshBody>, request_id: RequestId) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["POST", "/auth/refresh"])
+        .inc();
+
+    let timer = state.prometheus.http_request_duration_seconds
+        .with_label_values(&["POST", "/auth/refresh"])
+        .start_timer();
+
+    let claims = state.auth.verify(&body.refresh_token, TokenKind::Refresh)?;
+
+    match sqlx::query("DELETE FROM refresh_tokens WHERE jti = $1 AND user_id = $2 AND expires_at > CURRENT_TIMESTAMP")
+        .bind(&claims.jti)
+        .bind(claims.sub)
+        .execute(&state.db)
+        .await
+    {
+        Ok(result) if result.rows_affected() > 0 => {
+            let tokens = issue_tokens(&state, claims.sub, &request_id).await?;
+            timer.observe_duration();
+            Ok(HttpResponse::Ok().json(tokens))
+        },
+        Ok(_) => Err(ApiError::Unauthorized("Refresh token was already used or revoked")),
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to refresh tokens"))
+        },
+    }
+}
+
+/// Revokes a refresh token. Access tokens stay valid until they expire.
+#[post("/auth/logout")]
+pub async fn logout(state: Data<AppState>, body: Json<RefreshBody>, request_id: RequestId) -> Result<HttpResponse, ApiError> {
+    state.prometheus.http_requests_total
+        .with_label_values(&["POST", "/auth/logout"])
+        .inc();
+
+    let claims = state.auth.verify(&body.refresh_token, TokenKind::Refresh)?;
+
+    match sqlx::query("DELETE FROM refresh_tokens WHERE jti = $1")
+        .bind(&claims.jti)
+        .execute(&state.db)
+        .await
+    {
+        Ok(_) => Ok(HttpResponse::NoContent().finish()),
+        Err(err) => {
+            println!("[{}] {:?}", request_id.0, err);
+            Err(ApiError::Internal("Failed to log out"))
+        },
+    }
+}
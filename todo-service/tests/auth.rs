@@ -0,0 +1,96 @@
+mod common;
+
+use reqwest::Client;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn post(uri: &str, path: &str, body: Value) -> (u16, Value) {
+    common::send(Client::new().post(format!("{}{}", uri, path)), Some(&body)).await
+}
+
+/// Registers a user with a unique email and returns their credentials.
+async fn register(uri: &str) -> Value {
+    let credentials = json!({ "email": format!("auth-{}@example.com", Uuid::new_v4()), "password": "correct horse battery" });
+    let (status, user) = post(uri, "/auth/register", credentials.clone()).await;
+    assert_eq!((status, &user["email"]), (201, &credentials["email"]));
+    credentials
+}
+
+async fn todos_status(uri: &str, token: &str) -> u16 {
+    Client::new().get(format!("{}/todos", uri)).bearer_auth(token).send().await.unwrap().status().as_u16()
+}
+
+#[actix_web::test]
+async fn registers_and_logs_in() {
+    let uri = common::start().await;
+    let credentials = register(&uri).await;
+
+    assert_eq!(post(&uri, "/auth/register", credentials.clone()).await.0, 409);
+    let (status, tokens) = post(&uri, "/auth/login", credentials.clone()).await;
+    assert_eq!((status, &tokens["token_type"]), (200, &json!("Bearer")));
+    assert_eq!(todos_status(&uri, tokens["access_token"].as_str().unwrap()).await, 200);
+
+    // Emails are matched case-insensitively
+    let upper = json!({ "email": credentials["email"].as_str().unwrap().to_uppercase(), "password": credentials["password"] });
+    assert_eq!(post(&uri, "/auth/login", upper).await.0, 200);
+}
+
+#[actix_web::test]
+async fn rejects_bad_passwords_and_missing_tokens_with_401() {
+    let uri = common::start().await;
+    let credentials = register(&uri).await;
+
+    let wrong = json!({ "email": credentials["email"], "password": "incorrect horse battery" });
+    let (status, problem) = post(&uri, "/auth/login", wrong).await;
+    assert_eq!((status, problem["detail"].as_str()), (401, Some("Invalid email or password")));
+    let unknown = json!({ "email": "nobody@example.com", "password": "correct horse battery" });
+    assert_eq!(post(&uri, "/auth/login", unknown).await.0, 401);
+
+    let response = Client::new().get(format!("{}/todos", uri)).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+    assert_eq!(todos_status(&uri, "not-a-token").await, 401);
+}
+
+#[actix_web::test]
+async fn refresh_tokens_are_single_use() {
+    let uri = common::start().await;
+    let credentials = register(&uri).await;
+    let (_, tokens) = post(&uri, "/auth/login", credentials).await;
+    let refresh = json!({ "refresh_token": tokens["refresh_token"] });
+
+    let (status, renewed) = post(&uri, "/auth/refresh", refresh.clone()).await;
+    assert_eq!(status, 200);
+    assert_ne!(renewed["refresh_token"], tokens["refresh_token"]);
+    assert_eq!(todos_status(&uri, renewed["access_token"].as_str().unwrap()).await, 200);
+
+    let (status, problem) = post(&uri, "/auth/refresh", refresh).await;
+    assert_eq!((status, problem["detail"].as_str()), (401, Some("Refresh token was already used or revoked")));
+    assert_eq!(post(&uri, "/auth/refresh", json!({ "refresh_token": renewed["refresh_token"] })).await.0, 200);
+}
+
+#[actix_web::test]
+async fn logout_revokes_the_refresh_token() {
+    let uri = common::start().await;
+    let credentials = register(&uri).await;
+    let (_, tokens) = post(&uri, "/auth/login", credentials).await;
+    let refresh = json!({ "refresh_token": tokens["refresh_token"] });
+
+    assert_eq!(post(&uri, "/auth/logout", refresh.clone()).await.0, 204);
+    assert_eq!(post(&uri, "/auth/refresh", refresh).await.0, 401);
+}
+
+#[actix_web::test]
+async fn access_and_refresh_tokens_are_not_interchangeable() {
+    let uri = common::start().await;
+    let credentials = register(&uri).await;
+    let (_, tokens) = post(&uri, "/auth/login", credentials).await;
+    let (access, refresh) = (tokens["access_token"].as_str().unwrap(), tokens["refresh_token"].as_str().unwrap());
+
+    assert_eq!(todos_status(&uri, refresh).await, 401);
+    assert_eq!(post(&uri, "/auth/refresh", json!({ "refresh_token": access })).await.0, 401);
+    assert_eq!(post(&uri, "/auth/logout", json!({ "refresh_token": access })).await.0, 401);
+
+    // Neither attempt used up the refresh token
+    assert_eq!(post(&uri, "/auth/refresh", json!({ "refresh_token": refresh })).await.0, 200);
+}
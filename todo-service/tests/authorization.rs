@@ -0,0 +1,156 @@
+mod common;
+
+use common::User;
+use reqwest::Method;
+use serde_json::{json, Value};
+use todo_service::authz::{Action, ListRole};
+
+#[test]
+fn permissions_by_role() {
+    for role in ListRole::ALL {
+        for action in Action::ALL {
+            let expected = match action {
+                Action::View => true,
+                Action::EditTodos => role != ListRole::Viewer,
+                Action::Rename | Action::ManageMembers | Action::Delete => role == ListRole::Owner,
+            };
+            assert_eq!(role.can(action), expected, "{:?} {:?}", role, action);
+        }
+    }
+}
+
+/// The users of a test: `owner` creates each list and shares it with `editor`, `viewer` and `extra`.
+struct Users {
+    owner: User,
+    editor: User,
+    viewer: User,
+    extra: User,
+    outsider: User,
+}
+
+impl Users {
+    async fn register(uri: &str) -> Self {
+        Users {
+            owner: User::register(uri, "owner").await,
+            editor: User::register(uri, "editor").await,
+            viewer: User::register(uri, "viewer").await,
+            extra: User::register(uri, "extra").await,
+            outsider: User::register(uri, "outsider").await,
+        }
+    }
+
+    /// Creates a shared list with one todo, and returns their ids.
+    async fn list(&self, uri: &str) -> (i64, i64) {
+        let (status, list) = self.owner.send(Method::POST, &format!("{}/lists", uri), Some(json!({ "name": "Groceries" }))).await;
+        assert_eq!(status, 201);
+        let list_id = list["id"].as_i64().unwrap();
+        for (user, role) in [(&self.editor, "editor"), (&self.viewer, "viewer"), (&self.extra, "viewer")] {
+            let body = json!({ "email": user.email, "role": role });
+            let (status, _) = self.owner.send(Method::POST, &format!("{}/lists/{}/members", uri, list_id), Some(body)).await;
+            assert_eq!(status, 201);
+        }
+        let (status, todo) = self.owner.send(Method::POST, &format!("{}/lists/{}/todos", uri, list_id), Some(json!({ "title": "Milk" }))).await;
+        assert_eq!(status, 200);
+        (list_id, todo["id"].as_i64().unwrap())
+    }
+}
+
+/// A request on a list, the action it needs and its status when allowed.
+struct Case {
+    method: Method,
+    path: &'static str,
+    body: Option<Value>,
+    action: Action,
+    success: u16,
+}
+
+fn cases(users: &Users) -> Vec<Case> {
+    let case = |method, path, body, action, success| Case { method, path, body, action, success };
+    vec![
+        case(Method::GET, "", None, Action::View, 200),
+        case(Method::PATCH, "", Some(json!({ "name": "Renamed" })), Action::Rename, 200),
+        case(Method::DELETE, "", None, Action::Delete, 204),
+        case(Method::GET, "/members", None, Action::View, 200),
+        case(Method::POST, "/members", Some(json!({ "email": users.outsider.email, "role": "viewer" })), Action::ManageMembers, 201),
+        case(Method::PUT, "/members/{extra}", Some(json!({ "role": "editor" })), Action::ManageMembers, 200),
+        case(Method::DELETE, "/members/{extra}", None, Action::ManageMembers, 204),
+        case(Method::GET, "/todos", None, Action::View, 200),
+        case(Method::POST, "/todos", Some(json!({ "title": "Eggs" })), Action::EditTodos, 200),
+        case(Method::GET, "/todos/{todo}", None, Action::View, 200),
+        case(Method::PUT, "/todos/{todo}", Some(json!({ "title": "Oat milk", "status": "in_progress" })), Action::EditTodos, 200),
+        case(Method::PATCH, "/todos/{todo}", Some(json!({ "status": "completed" })), Action::EditTodos, 200),
+        case(Method::DELETE, "/todos/{todo}", None, Action::EditTodos, 204),
+    ]
+}
+
+#[actix_web::test]
+async fn every_role_and_action() {
+    let uri = common::start().await;
+    let users = Users::register(&uri).await;
+    let actors = [
+        (&users.owner, Some(ListRole::Owner)),
+        (&users.editor, Some(ListRole::Editor)),
+        (&users.viewer, Some(ListRole::Viewer)),
+        (&users.outsider, None),
+    ];
+
+    for case in cases(&users) {
+        for (actor, role) in actors {
+            let (list_id, todo_id) = users.list(&uri).await;
+            let path = case.path.replace("{extra}", &users.extra.id.to_string()).replace("{todo}", &todo_id.to_string());
+            let url = format!("{}/lists/{}{}", uri, list_id, path);
+
+            let (status, _) = actor.send(case.method.clone(), &url, case.body.clone()).await;
+            let expected = match role {
+                None => 404,
+                Some(role) if role.can(case.action) => case.success,
+                Some(_) => 403,
+            };
+            assert_eq!(status, expected, "{} {} as {:?}", case.method, case.path, role);
+        }
+    }
+}
+
+#[actix_web::test]
+async fn members_can_leave_but_a_list_keeps_an_owner() {
+    let uri = common::start().await;
+    let users = Users::register(&uri).await;
+    let (list_id, _) = users.list(&uri).await;
+    let member = |user: &User| format!("{}/lists/{}/members/{}", uri, list_id, user.id);
+
+    let (status, _) = users.viewer.send(Method::DELETE, &member(&users.viewer), None).await;
+    assert_eq!(status, 204);
+    let (status, _) = users.viewer.send(Method::GET, &format!("{}/lists/{}", uri, list_id), None).await;
+    assert_eq!(status, 404);
+
+    let (status, _) = users.owner.send(Method::DELETE, &member(&users.owner), None).await;
+    assert_eq!(status, 409);
+    let (status, _) = users.owner.send(Method::PUT, &member(&users.owner), Some(json!({ "role": "editor" }))).await;
+    assert_eq!(status, 409);
+
+    // Once the editor is promoted, the original owner can step down
+    let (status, _) = users.owner.send(Method::PUT, &member(&users.editor), Some(json!({ "role": "owner" }))).await;
+    assert_eq!(status, 200);
+    let (status, body) = users.owner.send(Method::PUT, &member(&users.owner), Some(json!({ "role": "viewer" }))).await;
+    assert_eq!((status, body["role"].as_str()), (200, Some("viewer")));
+    let (status, _) = users.owner.send(Method::DELETE, &member(&users.extra), None).await;
+    assert_eq!(status, 403);
+}
+
+#[actix_web::test]
+async fn list_todos_are_not_personal_todos() {
+    let uri = common::start().await;
+    let users = Users::register(&uri).await;
+    let (list_id, todo_id) = users.list(&uri).await;
+
+    let (status, _) = users.owner.send(Method::GET, &format!("{}/todos/{}", uri, todo_id), None).await;
+    assert_eq!(status, 404);
+    let (_, page) = users.owner.send(Method::GET, &format!("{}/todos", uri), None).await;
+    assert_eq!(page["total"], 0);
+
+    // A todo of another list isn't reachable through a list the user is a member of
+    let (other_list, other_todo) = users.list(&uri).await;
+    assert_ne!(other_list, list_id);
+    let (status, _) = users.viewer.send(Method::GET, &format!("{}/lists/{}/todos/{}", uri, list_id, other_todo), None).await;
+    assert_eq!(status, 404);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::User;
+use reqwest::{Method, RequestBuilder};
+use serde_json::Value;
+
+/// Sends `request` and checks it fails with a problem+json body of `status`, returning the body.
+async fn problem(request: RequestBuilder, status: u16) -> Value {
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), status);
+    assert_eq!(response.headers()["content-type"], "application/problem+json");
+    let problem: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(problem["type"], "about:blank");
+    assert_eq!(problem["status"], status);
+    assert!(problem["title"].is_string());
+    assert!(problem["detail"].is_string());
+    problem
+}
+
+#[actix_web::test]
+async fn body_errors_are_problems() {
+    let uri = common::start().await;
+    let user = User::register(&uri, "errors").await;
+    let url = format!("{}/todo", uri);
+    let post = || user.request(Method::POST, &url);
+
+    let body = problem(post().header("content-type", "application/json").body("{ \"title\": "), 400).await;
+    assert_eq!(body["title"], "Bad Request");
+    assert!(body["detail"].as_str().unwrap().starts_with("Invalid JSON"));
+
+    let body = problem(post().header("content-type", "text/plain").body("{}"), 415).await;
+    assert_eq!(body["detail"], "Content-Type must be application/json");
+
+    // Well-formed JSON of the wrong shape is a field problem
+    let request = post().header("content-type", "application/json").body(r#"{ "title": "Write", "status": "done" }"#);
+    let body = problem(request, 422).await;
+    assert_eq!(body["title"], "Unprocessable Entity");
+    assert!(body["errors"][0]["message"].as_str().unwrap().contains("unknown variant"));
+
+    let request = post().header("content-type", "application/json").body(r#"{ "title": "" }"#);
+    let body = problem(request, 422).await;
+    assert_eq!(body["errors"][0]["field"], "title");
+    assert_eq!(body["errors"][0]["message"], "Title must not be empty");
+}
+
+#[actix_web::test]
+async fn query_and_path_errors_are_problems() {
+    let uri = common::start().await;
+    let user = User::register(&uri, "errors").await;
+
+    let body = problem(user.request(Method::GET, &format!("{}/todos?status=done", uri)), 400).await;
+    assert!(body["detail"].as_str().unwrap().starts_with("Invalid query string"));
+    assert!(body.get("errors").is_none());
+
+    let body = problem(user.request(Method::GET, &format!("{}/todos/abc", uri)), 404).await;
+    assert_eq!((&body["title"], &body["detail"]), (&Value::from("Not Found"), &Value::from("Not found")));
+    problem(user.request(Method::DELETE, &format!("{}/lists/abc/todos/1", uri)), 404).await;
+
+    // Missing credentials are problems too
+    problem(reqwest::Client::new().get(format!("{}/todos", uri)), 401).await;
+}
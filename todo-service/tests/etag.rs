@@ -0,0 +1,75 @@
+mod common;
+
+use common::User;
+use reqwest::{Method, Response};
+use serde_json::{json, Value};
+
+fn etag(response: &Response) -> String {
+    response.headers()["etag"].to_str().unwrap().to_string()
+}
+
+/// Creates a todo and returns its URL and ETag.
+async fn create_todo(uri: &str, user: &User) -> (String, String) {
+    let request = common::json(user.request(Method::POST, &format!("{}/todo", uri)), &json!({ "title": "Write report" }));
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let etag = etag(&response);
+    let todo: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    (format!("{}/todos/{}", uri, todo["id"]), etag)
+}
+
+#[actix_web::test]
+async fn reads_are_not_modified_until_the_todo_changes() {
+    let uri = common::start().await;
+    let user = User::register(&uri, "etag").await;
+    let (url, created) = create_todo(&uri, &user).await;
+
+    let response = user.request(Method::GET, &url).send().await.unwrap();
+    assert_eq!((response.status().as_u16(), etag(&response)), (200, created.clone()));
+    let response = user.request(Method::GET, &url).header("if-none-match", &created).send().await.unwrap();
+    assert_eq!((response.status().as_u16(), etag(&response)), (304, created.clone()));
+    assert!(response.text().await.unwrap().is_empty());
+
+    let (status, todo) = user.send(Method::PATCH, &url, Some(json!({ "status": "in_progress" }))).await;
+    assert_eq!((status, todo["version"].as_i64()), (200, Some(2)));
+    let response = user.request(Method::GET, &url).header("if-none-match", &created).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_ne!(etag(&response), created);
+}
+
+#[actix_web::test]
+async fn writes_with_a_stale_if_match_fail_with_412() {
+    let uri = common::start().await;
+    let user = User::register(&uri, "etag").await;
+    let (url, first) = create_todo(&uri, &user).await;
+
+    // Two clients read the same version, the first write wins
+    let request = common::json(user.request(Method::PATCH, &url), &json!({ "title": "Write the report" }));
+    let response = request.header("if-match", &first).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let second = etag(&response);
+    assert_ne!(second, first);
+
+    let body = json!({ "title": "Write a report", "status": "pending" });
+    let response = common::json(user.request(Method::PUT, &url), &body).header("if-match", &first).send().await.unwrap();
+    assert_eq!(response.status(), 412);
+    let response = common::json(user.request(Method::PATCH, &url), &body).header("if-match", &first).send().await.unwrap();
+    assert_eq!(response.status(), 412);
+    let response = user.request(Method::DELETE, &url).header("if-match", &first).send().await.unwrap();
+    assert_eq!(response.status(), 412);
+
+    // Weak tags never match
+    let response = user.request(Method::DELETE, &url).header("if-match", format!("W/{}", second)).send().await.unwrap();
+    assert_eq!(response.status(), 412);
+
+    let (_, todo) = user.send(Method::GET, &url, None).await;
+    assert_eq!(todo["title"], "Write the report");
+
+    let response = common::json(user.request(Method::PUT, &url), &body).header("if-match", &second).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let third = etag(&response);
+    let response = user.request(Method::DELETE, &url).header("if-match", format!("{}, {}", second, third)).send().await.unwrap();
+    assert_eq!(response.status(), 204);
+    let response = user.request(Method::DELETE, &url).header("if-match", "*").send().await.unwrap();
+    assert_eq!(response.status(), 404);
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use std::time::Duration;
+
+use common::User;
+use reqwest::{Method, Response};
+use serde_json::{json, Value};
+
+async fn create(uri: &str, user: &User, key: &str, body: &Value) -> Response {
+    let request = user.request(Method::POST, &format!("{}/todo", uri)).header("idempotency-key", key);
+    common::json(request, body).send().await.unwrap()
+}
+
+async fn status_and_body(response: Response) -> (u16, Value) {
+    let status = response.status().as_u16();
+    (status, serde_json::from_str(&response.text().await.unwrap()).unwrap())
+}
+
+async fn total(uri: &str, user: &User) -> i64 {
+    user.send(Method::GET, &format!("{}/todos", uri), None).await.1["total"].as_i64().unwrap()
+}
+
+#[actix_web::test]
+async fn retries_return_the_created_todo() {
+    let uri = common::start().await;
+    let user = User::register(&uri, "retry").await;
+    let body = json!({ "title": "Pay rent", "due_date": "2024-11-01" });
+
+    let first = create(&uri, &user, "rent-november", &body).await;
+    assert!(first.headers().get("idempotent-replayed").is_none());
+    let (status, todo) = status_and_body(first).await;
+    assert_eq!(status, 200);
+
+    // The todo changes in between, the retry still gets the original response
+    let url = format!("{}/todos/{}", uri, todo["id"]);
+    user.send(Method::PATCH, &url, Some(json!({ "status": "completed" }))).await;
+
+    // Same body with the keys in another order
+    let retry = create(&uri, &user, "rent-november", &json!({ "due_date": "2024-11-01", "title": "Pay rent" })).await;
+    assert_eq!(retry.headers()["idempotent-replayed"], "true");
+    assert_eq!(retry.headers()["etag"], "\"1\"");
+    assert_eq!(status_and_body(retry).await, (200, todo));
+    assert_eq!(total(&uri, &user).await, 1);
+
+    // Keys belong to a user
+    let other = User::register(&uri, "retry").await;
+    let (status, _) = status_and_body(create(&uri, &other, "rent-november", &body).await).await;
+    assert_eq!(status, 200);
+    assert_eq!(total(&uri, &other).await, 1);
+}
+
+#[actix_web::test]
+async fn reusing_a_key_for_another_request_is_422() {
+    let uri = common::start().await;
+    let user = User::register(&uri, "reuse").await;
+
+    // A rejected request doesn't use up its key
+    let (status, _) = status_and_body(create(&uri, &user, "key-1", &json!({ "title": " " })).await).await;
+    assert_eq!(status, 422);
+    let (status, _) = status_and_body(create(&uri, &user, "key-1", &json!({ "title": "Call mum" })).await).await;
+    assert_eq!(status, 200);
+
+    let (status, problem) = status_and_body(create(&uri, &user, "key-1", &json!({ "title": "Call dad" })).await).await;
+    assert_eq!(status, 422);
+    assert_eq!(problem["errors"][0]["message"], "Idempotency-Key was already used with a different request");
+    assert_eq!(total(&uri, &user).await, 1);
+
+    let response = create(&uri, &user, &"k".repeat(256), &json!({ "title": "Call dad" })).await;
+    assert_eq!(response.status(), 400);
+}
+
+#[actix_web::test]
+async fn concurrent_requests_with_one_key_create_one_todo() {
+    let uri = common::start().await;
+    let user = User::register(&uri, "concurrent").await;
+    let body = json!({ "title": "Book flights" });
+
+    let responses = futures::future::join_all((0..5).map(|_| create(&uri, &user, "flights", &body))).await;
+    let mut ids = Vec::new();
+    for response in responses {
+        let (status, todo) = status_and_body(response).await;
+        assert_eq!(status, 200);
+        ids.push(todo["id"].clone());
+    }
+    ids.dedup();
+    assert_eq!(ids.len(), 1);
+    assert_eq!(total(&uri, &user).await, 1);
+}
+
+#[actix_web::test]
+async fn keys_expire() {
+    let uri = common::start_with(|service| service.with_idempotency_ttl(Duration::from_secs(1))).await;
+    let user = User::register(&uri, "expiry").await;
+
+    let (_, first) = status_and_body(create(&uri, &user, "weekly", &json!({ "title": "Water plants" })).await).await;
+    actix_web::rt::time::sleep(Duration::from_millis(1_100)).await;
+    let (status, second) = status_and_body(create(&uri, &user, "weekly", &json!({ "title": "Water the plants" })).await).await;
+    assert_eq!(status, 200);
+    assert_ne!(first["id"], second["id"]);
+}
+
+#[actix_web::test]
+async fn a_key_without_a_recorded_response_is_409() {
+    let (uri, db) = (common::start().await, common::database().await);
+    let user = User::register(&uri, "pending").await;
+    let body = json!({ "title": "Pay rent", "description": null, "status": null, "due_date": null });
+    sqlx::query(
+        "INSERT INTO idempotency_keys (user_id, key, request, expires_at)
+        VALUES ($1, 'rent-december', $2, CURRENT_TIMESTAMP + INTERVAL '1 hour')",
+    )
+    .bind(user.id as i32)
+    .bind(&body)
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let (status, problem) = status_and_body(create(&uri, &user, "rent-december", &body).await).await;
+    assert_eq!((status, problem["detail"].as_str()), (409, Some("A request with this Idempotency-Key is in progress")));
+    assert_eq!(total(&uri, &user).await, 0);
+}
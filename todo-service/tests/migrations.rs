@@ -0,0 +1,27 @@
+mod common;
+
+use todo_service::migrate;
+
+#[actix_web::test]
+async fn legacy_statuses_are_mapped_to_the_enum() {
+    let pool = common::empty_database("todo_service_migration_test").await;
+    // A table set up by hand from todo.sql, with statuses the enum doesn't have
+    sqlx::raw_sql(include_str!("../migrations/0001_create_todo.sql")).execute(&pool).await.unwrap();
+    sqlx::query(
+        "INSERT INTO todo (title, status) VALUES
+            ('a', 'pending'), ('b', 'in_progress'), ('c', 'done'), ('d', 'todo'), ('e', NULL), ('f', 'Whatever')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    migrate::run(&pool).await.unwrap();
+
+    let statuses: Vec<(String, String)> = sqlx::query_as("SELECT title, status::text FROM todo ORDER BY title")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    let expected = [("a", "pending"), ("b", "in_progress"), ("c", "completed"), ("d", "pending"), ("e", "pending"), ("f", "pending")];
+    assert_eq!(statuses, expected.map(|(title, status)| (title.to_string(), status.to_string())));
+    assert_eq!(migrate::check(&pool).await.unwrap(), 0);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::User;
+use reqwest::Method;
+use serde_json::{json, Value};
+
+/// Registers a user with `count` todos, titled `Todo 0`, `Todo 1`, ...
+async fn user_with_todos(uri: &str, count: usize) -> User {
+    let user = User::register(uri, "pages").await;
+    for n in 0..count {
+        let (status, _) = user.send(Method::POST, &format!("{}/todo", uri), Some(json!({ "title": format!("Todo {}", n) }))).await;
+        assert_eq!(status, 200);
+    }
+    user
+}
+
+fn ids(page: &Value) -> Vec<i64> {
+    page["items"].as_array().unwrap().iter().map(|todo| todo["id"].as_i64().unwrap()).collect()
+}
+
+#[actix_web::test]
+async fn rejects_invalid_limit_offset_and_sort() {
+    let uri = common::start().await;
+    let user = user_with_todos(&uri, 0).await;
+
+    for query in ["limit=0", "limit=101", "limit=-1", "offset=-1", "sort=priority", "sort=--id", "limit=ten"] {
+        let (status, problem) = user.send(Method::GET, &format!("{}/todos?{}", uri, query), None).await;
+        assert_eq!((status, &problem["status"]), (400, &json!(400)), "{}", query);
+    }
+    for query in ["limit=1", "limit=100", "offset=0", "sort=-due_date", "sort=title"] {
+        assert_eq!(user.send(Method::GET, &format!("{}/todos?{}", uri, query), None).await.0, 200, "{}", query);
+    }
+}
+
+#[actix_web::test]
+async fn cursor_pages_cover_every_todo_once() {
+    let uri = common::start().await;
+    let user = user_with_todos(&uri, 5).await;
+
+    for sort in ["created_at", "-created_at", "id", "-id"] {
+        let mut seen = Vec::new();
+        let mut url = format!("{}/todos?limit=2&sort={}", uri, sort);
+        loop {
+            let (status, page) = user.send(Method::GET, &url, None).await;
+            assert_eq!((status, &page["total"]), (200, &json!(5)));
+            seen.extend(ids(&page));
+            let Some(cursor) = page["next_cursor"].as_str() else { break };
+            url = format!("{}/todos?limit=2&sort={}&cursor={}", uri, sort, cursor);
+        }
+
+        let (_, all) = user.send(Method::GET, &format!("{}/todos?limit=100&sort={}", uri, sort), None).await;
+        assert_eq!(seen, ids(&all), "{}", sort);
+    }
+}
+
+#[actix_web::test]
+async fn rejects_cursors_with_offset_or_other_sorts() {
+    let uri = common::start().await;
+    let user = user_with_todos(&uri, 3).await;
+    let (_, page) = user.send(Method::GET, &format!("{}/todos?limit=1", uri), None).await;
+    let cursor = page["next_cursor"].as_str().unwrap();
+
+    let url = format!("{}/todos?limit=1&cursor={}&offset=1", uri, cursor);
+    let (status, problem) = user.send(Method::GET, &url, None).await;
+    assert_eq!((status, problem["detail"].as_str()), (400, Some("cursor and offset can't be combined")));
+
+    let url = format!("{}/todos?limit=1&cursor={}&sort=title", uri, cursor);
+    let (status, problem) = user.send(Method::GET, &url, None).await;
+    assert_eq!(
+        (status, problem["detail"].as_str()),
+        (400, Some("cursor is only supported when sorting by id or created_at")),
+    );
+
+    let (status, _) = user.send(Method::GET, &format!("{}/todos?cursor=not-a-cursor", uri), None).await;
+    assert_eq!(status, 400);
+
+    // Sorts without a cursor of their own don't hand one out
+    let (_, page) = user.send(Method::GET, &format!("{}/todos?limit=1&sort=title", uri), None).await;
+    assert_eq!(page["next_cursor"], Value::Null);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::User;
+use reqwest::Method;
+use serde_json::json;
+
+#[actix_web::test]
+async fn illegal_moves_are_409_with_the_allowed_targets() {
+    let uri = common::start().await;
+    let user = User::register(&uri, "status").await;
+    let (_, todo) = user.send(Method::POST, &format!("{}/todo", uri), Some(json!({ "title": "Write report" }))).await;
+    let url = format!("{}/todos/{}", uri, todo["id"]);
+
+    let (status, _) = user.send(Method::PATCH, &url, Some(json!({ "status": "completed" }))).await;
+    assert_eq!(status, 200);
+    let (status, problem) = user.send(Method::PATCH, &url, Some(json!({ "status": "pending" }))).await;
+    assert_eq!(
+        (status, problem["detail"].as_str()),
+        (409, Some("Can't move a todo from completed to pending, allowed: in_progress, archived")),
+    );
+
+    let body = json!({ "title": "Write report", "status": "archived" });
+    assert_eq!(user.send(Method::PUT, &url, Some(body)).await.0, 200);
+    let body = json!({ "title": "Write report", "status": "in_progress" });
+    let (status, problem) = user.send(Method::PUT, &url, Some(body)).await;
+    assert_eq!(
+        (status, problem["detail"].as_str()),
+        (409, Some("Can't move a todo from archived to in_progress, allowed: none")),
+    );
+
+    // The todo is left as it was
+    let (_, todo) = user.send(Method::GET, &url, None).await;
+    assert_eq!(todo["status"], "archived");
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::User;
+use reqwest::Method;
+use serde_json::{json, Value};
+
+/// Creates a todo and returns its URL.
+async fn create_todo(uri: &str, user: &User, body: Value) -> String {
+    let (status, todo) = user.send(Method::POST, &format!("{}/todo", uri), Some(body)).await;
+    assert_eq!(status, 200);
+    format!("{}/todos/{}", uri, todo["id"])
+}
+
+#[actix_web::test]
+async fn missing_todos_are_404() {
+    let uri = common::start().await;
+    let (owner, other) = (User::register(&uri, "owner").await, User::register(&uri, "other").await);
+    let url = create_todo(&uri, &owner, json!({ "title": "Write report" })).await;
+
+    // Someone else's todo is as missing as one that never existed
+    for url in [format!("{}/todos/2147483647", uri), url] {
+        let requests = [
+            (Method::GET, None),
+            (Method::PUT, Some(json!({ "title": "Write report", "status": "pending" }))),
+            (Method::PATCH, Some(json!({ "title": "Write report" }))),
+            (Method::DELETE, None),
+        ];
+        for (method, body) in requests {
+            let (status, problem) = other.send(method.clone(), &url, body).await;
+            assert_eq!(status, 404, "{} {}", method, url);
+            assert_eq!(problem["status"], 404);
+        }
+    }
+}
+
+#[actix_web::test]
+async fn rejects_blank_and_overlong_titles_with_422() {
+    let uri = common::start().await;
+    let user = User::register(&uri, "titles").await;
+    let url = create_todo(&uri, &user, json!({ "title": "Write report" })).await;
+
+    for title in ["   ".to_string(), "x".repeat(256)] {
+        let (status, problem) = user.send(Method::POST, &format!("{}/todo", uri), Some(json!({ "title": title }))).await;
+        assert_eq!((status, &problem["errors"][0]["field"]), (422, &json!("title")));
+
+        let body = json!({ "title": title, "status": "pending" });
+        assert_eq!(user.send(Method::PUT, &url, Some(body)).await.0, 422);
+        assert_eq!(user.send(Method::PATCH, &url, Some(json!({ "title": title }))).await.0, 422);
+    }
+
+    let (status, todo) = user.send(Method::POST, &format!("{}/todo", uri), Some(json!({ "title": "x".repeat(255) }))).await;
+    assert_eq!((status, todo["title"].as_str().map(str::len)), (200, Some(255)));
+}
+
+#[actix_web::test]
+async fn patch_with_null_clears_description_and_due_date() {
+    let uri = common::start().await;
+    let user = User::register(&uri, "patch").await;
+    let body = json!({ "title": "Write report", "description": "Quarterly", "due_date": "2030-01-31" });
+    let url = create_todo(&uri, &user, body).await;
+
+    // Fields left out are kept
+    let (status, todo) = user.send(Method::PATCH, &url, Some(json!({ "title": "Write the report" }))).await;
+    assert_eq!(status, 200);
+    assert_eq!((&todo["description"], &todo["due_date"]), (&json!("Quarterly"), &json!("2030-01-31")));
+
+    let (status, todo) = user.send(Method::PATCH, &url, Some(json!({ "description": null, "due_date": null }))).await;
+    assert_eq!(status, 200);
+    assert_eq!((&todo["title"], &todo["description"], &todo["due_date"]), (&json!("Write the report"), &Value::Null, &Value::Null));
+
+    let (status, todo) = user.send(Method::GET, &url, None).await;
+    assert_eq!((status, &todo["description"], &todo["due_date"]), (200, &Value::Null, &Value::Null));
+
+    // A patch must change something
+    assert_eq!(user.send(Method::PATCH, &url, Some(json!({}))).await.0, 422);
+}